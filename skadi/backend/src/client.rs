@@ -0,0 +1,212 @@
+use backend::{
+    command::CommandRequest,
+    err::BackendError,
+    event::Event,
+    hub::{self, ClientMessage},
+    stream::{self, EventReader, send_message},
+};
+use clap::ValueEnum;
+use std::{
+    env,
+    io::BufRead,
+    os::unix::process::CommandExt,
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+    time::Duration,
+};
+use tokio::{
+    io::AsyncWriteExt,
+    net::{
+        UnixStream,
+        unix::{OwnedReadHalf, OwnedWriteHalf},
+    },
+    sync::mpsc,
+};
+
+use crate::Args;
+
+const CONNECT_ATTEMPTS: u32 = 20;
+const CONNECT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+pub async fn run(args: &Args) -> Result<(), BackendError> {
+    let (mut events, mut write) = subscribe(args).await?;
+
+    let mut stdout = tokio::io::stdout();
+    let mut stdin = forward_stdin();
+
+    loop {
+        tokio::select! {
+            payload = events.next() => {
+                let Some(payload) = payload? else {
+                    break;
+                };
+
+                let line = serde_json::to_string(&payload).expect("Failed to serialize payload");
+                stdout.write_all(line.as_bytes()).await?;
+                stdout.write_all(b"\n").await?;
+                stdout.flush().await?;
+            }
+
+            // After stdin closes the write half stays open, the hub takes a shutdown as a disconnect
+            Some(line) = stdin.recv() => {
+                write.write_all(wrap_command(&line).as_bytes()).await?;
+                write.write_all(b"\n").await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Sends a single command and prints its reply, failing if the command did
+pub async fn send(args: &Args, request: &str) -> Result<(), BackendError> {
+    let request: CommandRequest =
+        serde_json::from_str(request).map_err(|e| BackendError::InvalidMessage(e.to_string()))?;
+    let id = request.id.clone();
+
+    let (mut events, mut write) = stream::subscribe(connect(args).await?, Some(vec![])).await?;
+    send_message(&mut write, &ClientMessage::Command(request)).await?;
+
+    while let Some(payload) = events.next().await? {
+        let Event::Reply(reply) = &payload.data else {
+            continue;
+        };
+
+        if reply.id.as_deref() != Some(id.as_str()) {
+            continue;
+        }
+
+        let line = serde_json::to_string(&payload).expect("Failed to serialize payload");
+        println!("{}", line);
+
+        return match &reply.error {
+            None if reply.ok => Ok(()),
+            error => Err(BackendError::CommandFailed(
+                format!("Command {}", id),
+                error
+                    .as_ref()
+                    .map_or_else(|| "no error given".to_string(), |e| e.to_string()),
+            )),
+        };
+    }
+
+    Err(BackendError::InvalidMessage(
+        "Hub closed the connection before replying".to_string(),
+    ))
+}
+
+// Subscribes to the opcodes from `--only`, or all of them
+pub async fn subscribe(
+    args: &Args,
+) -> Result<(EventReader<OwnedReadHalf>, OwnedWriteHalf), BackendError> {
+    let ops = (!args.only.is_empty()).then(|| args.only.clone());
+    stream::subscribe(connect(args).await?, ops).await
+}
+
+async fn connect(args: &Args) -> Result<UnixStream, BackendError> {
+    let path = hub::socket_path(args.simulate)?;
+
+    match UnixStream::connect(&path).await {
+        Ok(stream) => Ok(stream),
+        Err(_) => {
+            spawn_hub(args)?;
+            connect_with_retry(&path).await
+        }
+    }
+}
+
+// Stdin carries bare command requests, anything unparsable is passed on so the hub replies with the error
+fn wrap_command(line: &str) -> String {
+    match serde_json::from_str::<CommandRequest>(line) {
+        Ok(request) => serde_json::to_string(&ClientMessage::Command(request))
+            .expect("Failed to serialize client message"),
+        Err(_) => line.to_string(),
+    }
+}
+
+// A plain thread, since a pending read on tokio's stdin would keep the runtime from shutting down
+fn forward_stdin() -> mpsc::UnboundedReceiver<String> {
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+
+            if !line.trim().is_empty() && tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    rx
+}
+
+fn spawn_hub(args: &Args) -> Result<(), BackendError> {
+    // The hub's stderr goes nowhere, so a bad config is reported here instead
+    crate::load_config(args)?;
+
+    let exe = env::current_exe()?;
+    let mut command = Command::new(exe);
+
+    if let Some(config) = &args.config {
+        command.arg("--config").arg(config);
+    }
+
+    if let Some(interval) = args.sysinfo_poll_interval {
+        command
+            .arg("--sysinfo-poll-interval")
+            .arg(interval.to_string());
+    }
+
+    if let Some(mount) = &args.mount {
+        command.arg("--mount").arg(mount);
+    }
+
+    if let Some(player) = &args.player {
+        command.arg("--player").arg(player);
+    }
+
+    if let Some(sink) = &args.sink {
+        command.arg("--sink").arg(sink);
+    }
+
+    if args.simulate {
+        command.arg("--simulate");
+    }
+
+    for edge_case in &args.edge_cases {
+        let value = edge_case
+            .to_possible_value()
+            .expect("Edge cases are never skipped");
+        command.arg("--edge-cases").arg(value.get_name());
+    }
+
+    // Own process group, so the hub outlives the client that started it
+    command
+        .arg("serve")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()?;
+
+    Ok(())
+}
+
+async fn connect_with_retry(path: &Path) -> Result<UnixStream, BackendError> {
+    let mut attempt = 0;
+
+    loop {
+        match UnixStream::connect(path).await {
+            Ok(stream) => return Ok(stream),
+            Err(e) if attempt + 1 >= CONNECT_ATTEMPTS => return Err(e.into()),
+            Err(_) => {
+                attempt += 1;
+                tokio::time::sleep(CONNECT_RETRY_DELAY).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::{fs, path::Path};
+use ts_rs::TS;
+
+use crate::{
+    command::{Command, CommandReply, CommandRequest},
+    custom::CustomEvent,
+    err::{BackendError, ErrorPayload},
+    hub::ClientMessage,
+    hyprland::{
+        MonitorInfo, WindowChanged, WindowInfo, Workspace, WorkspaceEventKind, WorkspaceInfo,
+        WorkspaceState,
+    },
+    hyprland_event::HyprlandEvent,
+    payload::{Hello, Meta, OpCode, PROTOCOL_VERSION, Payload},
+    sources::{HealthStatus, SourceHealth},
+    spotify::{MediaAction, SpotifyEvent, SpotifyEventKind, TrackInfo},
+    sysinfo::SysinfoPayload,
+    volume::VolumeEvent,
+};
+
+const HEADER: &str = "// Generated by `backend codegen` from the Rust types, do not edit by hand";
+
+// Everything the frontend sees on the wire or sends back, in dependency order
+pub fn typescript() -> String {
+    let decls = [
+        OpCode::decl(),
+        Meta::decl(),
+        Payload::<()>::decl(),
+        Hello::decl(),
+        BackendError::decl(),
+        ErrorPayload::decl(),
+        SysinfoPayload::decl(),
+        WindowChanged::decl(),
+        WorkspaceEventKind::decl(),
+        Workspace::decl(),
+        WorkspaceInfo::decl(),
+        MonitorInfo::decl(),
+        WindowInfo::decl(),
+        WorkspaceState::decl(),
+        HyprlandEvent::decl(),
+        TrackInfo::decl(),
+        SpotifyEventKind::decl(),
+        SpotifyEvent::decl(),
+        VolumeEvent::decl(),
+        CustomEvent::decl(),
+        HealthStatus::decl(),
+        SourceHealth::decl(),
+        MediaAction::decl(),
+        Command::decl(),
+        CommandRequest::decl(),
+        CommandReply::decl(),
+        ClientMessage::decl(),
+    ];
+
+    let mut out = format!(
+        "{}\n\nexport const PROTOCOL_VERSION = {};\n",
+        HEADER, PROTOCOL_VERSION
+    );
+
+    for decl in decls {
+        out.push_str("\nexport ");
+        out.push_str(&decl);
+        out.push('\n');
+    }
+
+    out
+}
+
+pub fn write(out: Option<&Path>) -> Result<(), BackendError> {
+    match out {
+        Some(path) => fs::write(path, typescript())?,
+        None => print!("{}", typescript()),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payloads_ts_is_up_to_date() {
+        let checked_in = include_str!("../../plugins/payloads.ts");
+
+        assert!(
+            checked_in == typescript(),
+            "plugins/payloads.ts is stale, run `cargo run -- codegen --out ../plugins/payloads.ts`"
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{
+    process::{ExitStatus, Output, Stdio},
+    ptr,
+};
+use tokio::{
+    io::AsyncReadExt,
+    process::{self, ChildStdout},
+};
+use ts_rs::TS;
+
+use crate::{
+    err::BackendError,
+    hyprland,
+    payload::{OpCode, PayloadData, Retain},
+    spotify::{self, MediaAction},
+    volume,
+};
+
+#[derive(Debug, Serialize, Deserialize, TS, JsonSchema)]
+pub struct CommandRequest {
+    pub id: String,
+    pub command: Command,
+}
+
+#[derive(Debug, Serialize, Deserialize, TS, JsonSchema)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Command {
+    Workspace { id: i32 },
+    Media { action: MediaAction },
+    VolumeSet { volume: u32 },
+    VolumeStep { delta: i32 },
+    VolumeMute,
+}
+
+#[derive(Debug, Serialize, Deserialize, TS, JsonSchema)]
+pub struct CommandReply {
+    // `None` when the request was too malformed to read an id from
+    pub id: Option<String>,
+    pub ok: bool,
+    pub error: Option<BackendError>,
+}
+
+impl CommandReply {
+    pub fn new(id: Option<String>, result: Result<(), BackendError>) -> Self {
+        Self {
+            id,
+            ok: result.is_ok(),
+            error: result.err(),
+        }
+    }
+}
+
+impl Command {
+    // The module whose source carries the command out
+    pub fn module(&self) -> &'static str {
+        match self {
+            Command::Workspace { .. } => hyprland::MODULE,
+            Command::Media { .. } => spotify::MODULE,
+            Command::VolumeSet { .. } | Command::VolumeStep { .. } | Command::VolumeMute => {
+                volume::MODULE
+            }
+        }
+    }
+}
+
+impl PayloadData for CommandReply {
+    fn op(&self) -> OpCode {
+        OpCode::Reply
+    }
+
+    fn retain(&self) -> Retain {
+        Retain::Nothing
+    }
+}
+
+// Runs a program to completion, turning a non-zero exit into an error carrying its stderr
+pub async fn run(program: &str, args: &[&str]) -> Result<String, BackendError> {
+    let mut process = Process::spawn(
+        process::Command::new(program)
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped()),
+    )?;
+    let output = process.output().await?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(BackendError::CommandFailed(program.to_string(), stderr));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// A program in a process group of its own. Dropping it, which is what aborting the task holding it does,
+// kills the whole group so pipelines and background jobs go along, and reaps the program
+pub struct Process {
+    child: process::Child,
+    pid: i32,
+}
+
+impl Process {
+    pub fn spawn(command: &mut process::Command) -> Result<Self, BackendError> {
+        let child = command.process_group(0).spawn()?;
+        let pid = child
+            .id()
+            .expect("A program that was just spawned has an id") as i32;
+
+        Ok(Self { child, pid })
+    }
+
+    pub fn stdout(&mut self) -> ChildStdout {
+        self.child.stdout.take().expect("stdout is piped")
+    }
+
+    // Only waits for the program itself, whatever it left running is killed on drop
+    pub async fn wait(&mut self) -> Result<ExitStatus, BackendError> {
+        Ok(self.child.wait().await?)
+    }
+
+    // Both streams are read at once, so a program filling one of them can't stall on the other
+    async fn output(&mut self) -> Result<Output, BackendError> {
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let mut stdout_pipe = self.stdout();
+        let mut stderr_pipe = self.child.stderr.take().expect("stderr is piped");
+
+        tokio::try_join!(
+            stdout_pipe.read_to_end(&mut stdout),
+            stderr_pipe.read_to_end(&mut stderr)
+        )?;
+
+        Ok(Output {
+            status: self.wait().await?,
+            stdout,
+            stderr,
+        })
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        // SAFETY: plain syscalls on the group this process created. The program's pid can't be reused
+        // for another group before it is reaped, which is what happens right after
+        unsafe {
+            libc::kill(-self.pid, libc::SIGKILL);
+
+            // Already reaped once `wait` returned
+            if self.child.id().is_some() {
+                libc::waitpid(self.pid, ptr::null_mut(), 0);
+            }
+        }
+    }
+}
@@ -0,0 +1,374 @@
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env, fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::sync::mpsc;
+
+use crate::{custom, err::BackendError, hyprland, spotify, sysinfo, volume};
+
+pub const MODULE: &str = "config";
+
+// Taken by the built-in modules, which report errors and health under the same names
+const RESERVED_NAMES: [&str; 6] = [
+    MODULE,
+    sysinfo::MODULE,
+    hyprland::MODULE,
+    spotify::MODULE,
+    volume::MODULE,
+    custom::MODULE,
+];
+
+// Every section and field is optional, a missing file means all modules with their defaults
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub sysinfo: SysinfoConfig,
+    pub hyprland: HyprlandConfig,
+    pub spotify: SpotifyConfig,
+    pub volume: VolumeConfig,
+    // Keyed by the name its payloads are tagged with
+    pub custom: BTreeMap<String, CustomConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SysinfoConfig {
+    pub enabled: bool,
+    // Seconds between samples
+    pub poll_interval: u64,
+    // Where the disk figures are read from
+    pub mount: PathBuf,
+}
+
+impl Default for SysinfoConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            poll_interval: 5,
+            mount: PathBuf::from("/"),
+        }
+    }
+}
+
+impl SysinfoConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.poll_interval)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HyprlandConfig {
+    pub enabled: bool,
+}
+
+impl Default for HyprlandConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpotifyConfig {
+    pub enabled: bool,
+    // Any MPRIS player name playerctl accepts
+    pub player: String,
+}
+
+impl Default for SpotifyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            player: spotify::DEFAULT_PLAYER.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VolumeConfig {
+    pub enabled: bool,
+    // Any sink name pactl accepts
+    pub sink: String,
+}
+
+impl Default for VolumeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            sink: volume::DEFAULT_SINK.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomConfig {
+    // Run through `sh -c`
+    pub command: String,
+    // Seconds between runs, without it the command is kept running and followed line by line
+    #[serde(default)]
+    pub interval: Option<u64>,
+    #[serde(default)]
+    pub format: OutputFormat,
+}
+
+impl CustomConfig {
+    pub fn interval(&self) -> Option<Duration> {
+        self.interval.map(Duration::from_secs)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+// `$XDG_CONFIG_HOME/skadi/backend.toml`, falling back to `~/.config`
+pub fn config_path() -> Option<PathBuf> {
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(config_dir.join("skadi").join("backend.toml"))
+}
+
+impl Config {
+    // An explicit path must exist, the default one may not
+    pub fn load(path: Option<&Path>) -> Result<Self, BackendError> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match config_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound && !required => {
+                return Ok(Self::default());
+            }
+            Err(e) => {
+                return Err(BackendError::ConfigParse(
+                    path.display().to_string(),
+                    e.to_string(),
+                ));
+            }
+        };
+
+        Self::parse(&path, &contents)
+    }
+
+    pub fn parse(path: &Path, contents: &str) -> Result<Self, BackendError> {
+        toml::from_str(contents)
+            .map_err(|e| BackendError::ConfigParse(path.display().to_string(), e.to_string()))
+    }
+
+    // Modules whose section differs, they have to be restarted or stopped
+    pub fn changed_modules(&self, other: &Config) -> Vec<String> {
+        let mut changed: Vec<String> = [
+            (sysinfo::MODULE, self.sysinfo != other.sysinfo),
+            (hyprland::MODULE, self.hyprland != other.hyprland),
+            (spotify::MODULE, self.spotify != other.spotify),
+            (volume::MODULE, self.volume != other.volume),
+        ]
+        .into_iter()
+        .filter(|(_, changed)| *changed)
+        .map(|(module, _)| module.to_string())
+        .collect();
+
+        let names: BTreeSet<&String> = self.custom.keys().chain(other.custom.keys()).collect();
+
+        for name in names {
+            if self.custom.get(name) != other.custom.get(name) {
+                changed.push(name.clone());
+            }
+        }
+
+        changed
+    }
+
+    // Run after CLI overrides are applied, so those are checked too
+    pub fn validate(&self) -> Result<(), BackendError> {
+        if self.sysinfo.poll_interval == 0 {
+            return Err(invalid("sysinfo.poll_interval", "must be at least 1"));
+        }
+
+        if !self.sysinfo.mount.is_absolute() {
+            return Err(invalid("sysinfo.mount", "must be an absolute path"));
+        }
+
+        if self.spotify.player.trim().is_empty() {
+            return Err(invalid("spotify.player", "must not be empty"));
+        }
+
+        if self.volume.sink.trim().is_empty() {
+            return Err(invalid("volume.sink", "must not be empty"));
+        }
+
+        for (name, custom) in &self.custom {
+            let field = |key: &str| format!("custom.{}.{}", name, key);
+
+            if name.trim().is_empty() || RESERVED_NAMES.contains(&name.as_str()) {
+                return Err(invalid(
+                    &format!("custom.{}", name),
+                    "needs a name of its own, apart from the built-in modules",
+                ));
+            }
+
+            if custom.command.trim().is_empty() {
+                return Err(invalid(&field("command"), "must not be empty"));
+            }
+
+            if custom.interval == Some(0) {
+                return Err(invalid(&field("interval"), "must be at least 1"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn invalid(field: &str, reason: &str) -> BackendError {
+    BackendError::InvalidConfig(field.to_string(), reason.to_string())
+}
+
+// Watches the directory rather than the file, since editors tend to replace the file when saving.
+// The watcher stops when dropped
+pub fn watch(
+    path: &Path,
+) -> Result<(RecommendedWatcher, mpsc::UnboundedReceiver<()>), BackendError> {
+    let name = path.file_name().map(|name| name.to_os_string());
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+
+        // Reading the file ourselves shows up as an access, which must not trigger another reload
+        let written = matches!(
+            event.kind,
+            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+        );
+
+        if written
+            && event
+                .paths
+                .iter()
+                .any(|path| path.file_name() == name.as_deref())
+        {
+            let _ = tx.send(());
+        }
+    })
+    .map_err(|e| BackendError::ConfigWatch(e.to_string()))?;
+
+    watcher
+        .watch(dir, RecursiveMode::NonRecursive)
+        .map_err(|e| BackendError::ConfigWatch(e.to_string()))?;
+
+    Ok((watcher, rx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(contents: &str) -> Config {
+        Config::parse(Path::new("backend.toml"), contents).unwrap()
+    }
+
+    fn rejected(contents: &str) -> (String, String) {
+        match parse(contents).validate() {
+            Err(BackendError::InvalidConfig(field, reason)) => (field, reason),
+            other => panic!("Expected an invalid config, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn accepts_the_defaults() {
+        parse("").validate().unwrap();
+    }
+
+    #[test]
+    fn rejects_every_invalid_setting() {
+        let cases = [
+            ("[sysinfo]\npoll_interval = 0", "sysinfo.poll_interval"),
+            ("[sysinfo]\nmount = \"data\"", "sysinfo.mount"),
+            ("[spotify]\nplayer = \" \"", "spotify.player"),
+            ("[volume]\nsink = \"\"", "volume.sink"),
+            ("[custom.volume]\ncommand = \"true\"", "custom.volume"),
+            ("[custom.\" \"]\ncommand = \"true\"", "custom. "),
+            ("[custom.weather]\ncommand = \"\"", "custom.weather.command"),
+            (
+                "[custom.weather]\ncommand = \"curl wttr.in\"\ninterval = 0",
+                "custom.weather.interval",
+            ),
+        ];
+
+        for (contents, field) in cases {
+            assert_eq!(rejected(contents).0, field, "{}", contents);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        let error = Config::parse(Path::new("backend.toml"), "[sysinfo]\ninterval = 5");
+        assert!(matches!(error, Err(BackendError::ConfigParse(..))));
+    }
+
+    #[test]
+    fn reports_only_the_modules_that_changed() {
+        let old = parse(
+            r#"
+            [sysinfo]
+            poll_interval = 5
+
+            [custom.weather]
+            command = "curl wttr.in"
+            interval = 600
+
+            [custom.uptime]
+            command = "uptime"
+            interval = 60
+            "#,
+        );
+        let new = parse(
+            r#"
+            [sysinfo]
+            poll_interval = 5
+
+            [spotify]
+            enabled = false
+
+            [custom.weather]
+            command = "curl wttr.in"
+            interval = 300
+
+            [custom.updates]
+            command = "checkupdates"
+            interval = 3600
+            "#,
+        );
+
+        assert_eq!(
+            old.changed_modules(&new),
+            ["spotify", "updates", "uptime", "weather"]
+        );
+        assert!(new.changed_modules(&new.clone()).is_empty());
+    }
+}
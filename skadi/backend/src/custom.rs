@@ -0,0 +1,157 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use ts_rs::TS;
+
+use crate::payload::{OpCode, PayloadData, Retain};
+
+#[cfg(feature = "custom")]
+use {
+    crate::{
+        command::{self, Process},
+        config::{Config, CustomConfig, OutputFormat},
+        err::BackendError,
+        hub::Emitter,
+        sources::{EventSource, Source, SourceFuture},
+    },
+    std::{process::Stdio, sync::Arc, time::Duration},
+    tokio::{
+        io::{AsyncBufReadExt, BufReader},
+        process,
+    },
+};
+
+pub const MODULE: &str = "custom";
+
+// One line of output from a command defined under `[custom.<name>]`
+#[derive(Debug, Serialize, Deserialize, TS, JsonSchema)]
+pub struct CustomEvent {
+    pub source: String,
+    // A string for text output, any JSON value for JSON output
+    #[ts(type = "unknown")]
+    pub value: Value,
+}
+
+impl PayloadData for CustomEvent {
+    fn op(&self) -> OpCode {
+        OpCode::Custom
+    }
+
+    fn retain(&self) -> Retain {
+        Retain::Entry(self.source.clone())
+    }
+}
+
+// Every entry is a source of its own, so one failing command doesn't restart the others
+#[cfg(feature = "custom")]
+pub fn sources(config: &Config) -> Vec<Source> {
+    config
+        .custom
+        .iter()
+        .map(|(name, custom)| {
+            Arc::new(CustomSource {
+                name: name.clone(),
+                config: custom.clone(),
+            }) as Source
+        })
+        .collect()
+}
+
+#[cfg(feature = "custom")]
+pub struct CustomSource {
+    name: String,
+    config: CustomConfig,
+}
+
+#[cfg(feature = "custom")]
+impl EventSource for CustomSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn ops(&self) -> &'static [OpCode] {
+        &[OpCode::Custom]
+    }
+
+    // Every custom source emits `Custom`, each under its own name
+    fn cache_key(&self) -> Option<&str> {
+        Some(&self.name)
+    }
+
+    fn start(&self, emitter: Emitter) -> SourceFuture<'_, Result<(), BackendError>> {
+        Box::pin(async move {
+            match self.config.interval() {
+                Some(interval) => self.poll(emitter, interval).await,
+                None => self.follow(emitter).await,
+            }
+        })
+    }
+}
+
+#[cfg(feature = "custom")]
+impl CustomSource {
+    async fn poll(&self, emitter: Emitter, interval: Duration) -> Result<(), BackendError> {
+        let mut interval = tokio::time::interval(interval);
+
+        loop {
+            interval.tick().await;
+
+            let output = match command::run("sh", &["-c", &self.config.command]).await {
+                Ok(output) => output,
+                Err(BackendError::CommandFailed(_, stderr)) => {
+                    return Err(BackendError::CommandFailed(self.name.clone(), stderr));
+                }
+                Err(e) => return Err(e),
+            };
+
+            for line in output.lines() {
+                self.forward(&emitter, line);
+            }
+        }
+    }
+
+    async fn follow(&self, emitter: Emitter) -> Result<(), BackendError> {
+        let mut child = Process::spawn(
+            process::Command::new("sh")
+                .args(["-c", &self.config.command])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null()),
+        )?;
+
+        let mut lines = BufReader::new(child.stdout()).lines();
+
+        while let Some(line) = lines.next_line().await? {
+            self.forward(&emitter, &line);
+        }
+
+        let status = child.wait().await?;
+
+        if !status.success() {
+            return Err(BackendError::CommandFailed(
+                self.name.clone(),
+                status.to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn forward(&self, emitter: &Emitter, line: &str) {
+        let value = match self.config.format {
+            OutputFormat::Text => Value::String(line.to_string()),
+            OutputFormat::Json => match serde_json::from_str(line) {
+                Ok(value) => value,
+                Err(_) => {
+                    let error = BackendError::InvalidOutput(self.name.clone(), line.to_string());
+                    emitter.error(&self.name, error);
+                    return;
+                }
+            },
+        };
+
+        emitter.emit(CustomEvent {
+            source: self.name.clone(),
+            value,
+        });
+    }
+}
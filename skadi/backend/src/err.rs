@@ -1,8 +1,11 @@
-use serde::Serialize;
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, ser::SerializeStruct};
+use std::borrow::Cow;
+use ts_rs::TS;
 
-use crate::payload::{OpCode, PayloadData};
+use crate::payload::{OpCode, PayloadData, Retain};
 
-#[derive(Debug, Serialize, thiserror::Error)]
+#[derive(Debug, thiserror::Error)]
 pub enum BackendError {
     #[error("Tokio error: {0}")]
     TokioIo(String),
@@ -15,6 +18,157 @@ pub enum BackendError {
 
     #[error("Invalid OpCode: {0}")]
     InvalidOpCode(u16),
+
+    #[error("Invalid OpCode name: {0}")]
+    InvalidOpCodeName(String),
+
+    #[error("Another backend hub is already listening on {0}")]
+    HubAlreadyRunning(String),
+
+    #[error("Invalid message: {0}")]
+    InvalidMessage(String),
+
+    #[error("{0} failed: {1}")]
+    CommandFailed(String, String),
+
+    #[error("Unexpected output from {0}: {1}")]
+    InvalidOutput(String, String),
+
+    #[error("{0} exited")]
+    SourceExited(String),
+
+    #[error("Hub speaks protocol version {found}, this client expects {expected}")]
+    ProtocolMismatch { expected: u16, found: u16 },
+
+    #[error("Failed to read config {0}: {1}")]
+    ConfigParse(String, String),
+
+    #[error("Invalid config value for {0}: {1}")]
+    InvalidConfig(String, String),
+
+    #[error("Failed to watch config: {0}")]
+    ConfigWatch(String),
+
+    #[error("The {0} module is disabled")]
+    ModuleDisabled(String),
+
+    #[error("The {0} module is not compiled into this build")]
+    ModuleNotCompiled(String),
+
+    #[error("Invalid recording {0}: {1}")]
+    InvalidRecording(String, String),
+
+    #[error("The {0} module doesn't handle this command")]
+    UnsupportedCommand(String),
+
+    #[error("Hyprland refused {0}: {1}")]
+    HyprlandRequest(String, String),
+
+    // Read back from the stream, where only the kind and message survive
+    #[error("{message}")]
+    Remote { kind: String, message: String },
+}
+
+impl BackendError {
+    // Stable identifier for the frontend to match on, unlike the message
+    pub fn kind(&self) -> &str {
+        match self {
+            BackendError::TokioIo(_) => "io",
+            BackendError::XdgRuntimeDirNotSet => "xdgRuntimeDirNotSet",
+            BackendError::HyprlandSignatureNotSet => "hyprlandSignatureNotSet",
+            BackendError::InvalidOpCode(_) => "invalidOpCode",
+            BackendError::InvalidOpCodeName(_) => "invalidOpCodeName",
+            BackendError::HubAlreadyRunning(_) => "hubAlreadyRunning",
+            BackendError::InvalidMessage(_) => "invalidMessage",
+            BackendError::CommandFailed(..) => "commandFailed",
+            BackendError::InvalidOutput(..) => "invalidOutput",
+            BackendError::SourceExited(_) => "sourceExited",
+            BackendError::ProtocolMismatch { .. } => "protocolMismatch",
+            BackendError::ConfigParse(..) => "configParse",
+            BackendError::InvalidConfig(..) => "invalidConfig",
+            BackendError::ConfigWatch(_) => "configWatch",
+            BackendError::ModuleDisabled(_) => "moduleDisabled",
+            BackendError::ModuleNotCompiled(_) => "moduleNotCompiled",
+            BackendError::InvalidRecording(..) => "invalidRecording",
+            BackendError::UnsupportedCommand(_) => "unsupportedCommand",
+            BackendError::HyprlandRequest(..) => "hyprlandRequest",
+            BackendError::Remote { kind, .. } => kind,
+        }
+    }
+
+    // Missing environment won't fix itself, so restarting the source is pointless
+    pub fn is_retryable(&self) -> bool {
+        !matches!(
+            self,
+            BackendError::XdgRuntimeDirNotSet | BackendError::HyprlandSignatureNotSet
+        )
+    }
+}
+
+impl Serialize for BackendError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("BackendError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for BackendError {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Remote {
+            kind: String,
+            message: String,
+        }
+
+        let Remote { kind, message } = Remote::deserialize(deserializer)?;
+
+        Ok(BackendError::Remote { kind, message })
+    }
+}
+
+// Matches the serialization above rather than the variants
+impl TS for BackendError {
+    type WithoutGenerics = Self;
+    type OptionInnerType = Self;
+
+    fn name() -> String {
+        "BackendError".to_string()
+    }
+
+    fn decl() -> String {
+        format!("type BackendError = {};", Self::inline())
+    }
+
+    fn decl_concrete() -> String {
+        Self::decl()
+    }
+
+    fn inline() -> String {
+        "{ kind: string, message: string, }".to_string()
+    }
+
+    fn inline_flattened() -> String {
+        Self::inline()
+    }
+}
+
+impl JsonSchema for BackendError {
+    fn schema_name() -> Cow<'static, str> {
+        "BackendError".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "object",
+            "properties": {
+                "kind": { "type": "string" },
+                "message": { "type": "string" },
+            },
+            "required": ["kind", "message"],
+        })
+    }
 }
 
 impl From<tokio::io::Error> for BackendError {
@@ -23,8 +177,21 @@ impl From<tokio::io::Error> for BackendError {
     }
 }
 
-impl PayloadData for BackendError {
+#[derive(Debug, Serialize, Deserialize, TS, JsonSchema)]
+pub struct ErrorPayload {
+    pub module: String,
+    #[serde(flatten)]
+    pub error: BackendError,
+    // False once the module gave up, which the bar shows as degraded
+    pub retrying: bool,
+}
+
+impl PayloadData for ErrorPayload {
     fn op(&self) -> OpCode {
         OpCode::Error
     }
+
+    fn retain(&self) -> Retain {
+        Retain::Nothing
+    }
 }
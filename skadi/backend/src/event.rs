@@ -0,0 +1,185 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    command::CommandReply,
+    custom::CustomEvent,
+    err::{BackendError, ErrorPayload},
+    hyprland::{WindowChanged, Workspace, WorkspaceState},
+    hyprland_event::HyprlandEvent,
+    payload::{Hello, OpCode, Payload},
+    sources::SourceHealth,
+    spotify::SpotifyEvent,
+    sysinfo::SysinfoPayload,
+    volume::VolumeEvent,
+};
+
+// Any payload the hub sends, told apart by the opcode of its envelope
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum Event {
+    Error(ErrorPayload),
+    Sysinfo(SysinfoPayload),
+    WindowChanged(WindowChanged),
+    Workspace(Workspace),
+    SpotifyEvent(SpotifyEvent),
+    VolumeEvent(VolumeEvent),
+    Reply(CommandReply),
+    Health(SourceHealth),
+    Hello(Hello),
+    Custom(CustomEvent),
+    WorkspaceState(WorkspaceState),
+    Hyprland(HyprlandEvent),
+}
+
+impl Event {
+    pub fn op(&self) -> OpCode {
+        match self {
+            Event::Error(_) => OpCode::Error,
+            Event::Sysinfo(_) => OpCode::Sysinfo,
+            Event::WindowChanged(_) => OpCode::WindowChanged,
+            Event::Workspace(_) => OpCode::Workspace,
+            Event::SpotifyEvent(_) => OpCode::SpotifyEvent,
+            Event::VolumeEvent(_) => OpCode::VolumeEvent,
+            Event::Reply(_) => OpCode::Reply,
+            Event::Health(_) => OpCode::Health,
+            Event::Hello(_) => OpCode::Hello,
+            Event::Custom(_) => OpCode::Custom,
+            Event::WorkspaceState(_) => OpCode::WorkspaceState,
+            Event::Hyprland(_) => OpCode::Hyprland,
+        }
+    }
+
+    pub fn decode(op: OpCode, data: Value) -> Result<Self, BackendError> {
+        let event = match op {
+            OpCode::Error => Event::Error(from_value(data)?),
+            OpCode::Sysinfo => Event::Sysinfo(from_value(data)?),
+            OpCode::WindowChanged => Event::WindowChanged(from_value(data)?),
+            OpCode::Workspace => Event::Workspace(from_value(data)?),
+            OpCode::SpotifyEvent => Event::SpotifyEvent(from_value(data)?),
+            OpCode::VolumeEvent => Event::VolumeEvent(from_value(data)?),
+            OpCode::Reply => Event::Reply(from_value(data)?),
+            OpCode::Health => Event::Health(from_value(data)?),
+            OpCode::Hello => Event::Hello(from_value(data)?),
+            OpCode::Custom => Event::Custom(from_value(data)?),
+            OpCode::WorkspaceState => Event::WorkspaceState(from_value(data)?),
+            OpCode::Hyprland => Event::Hyprland(from_value(data)?),
+        };
+
+        Ok(event)
+    }
+}
+
+impl Payload<Event> {
+    // Parses one line of the stream, the inverse of what the hub writes
+    pub fn parse(line: &str) -> Result<Self, BackendError> {
+        // Through `Value`, since `RawValue` can't sit next to the flattened metadata
+        let payload: Payload<Value> =
+            serde_json::from_str(line).map_err(|e| BackendError::InvalidMessage(e.to_string()))?;
+
+        let data = Event::decode(OpCode::try_from(payload.op)?, payload.data)?;
+
+        Ok(Payload {
+            op: payload.op,
+            meta: payload.meta,
+            snapshot: payload.snapshot,
+            data,
+        })
+    }
+}
+
+fn from_value<T: serde::de::DeserializeOwned>(data: Value) -> Result<T, BackendError> {
+    serde_json::from_value(data).map_err(|e| BackendError::InvalidMessage(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // A payload of every kind, as the hub would write it
+    fn sample(op: OpCode) -> Value {
+        match op {
+            OpCode::Error => json!({
+                "module": "volume", "retrying": true, "kind": "sourceExited",
+                "message": "pactl subscribe exited",
+            }),
+            OpCode::Sysinfo => json!({
+                "cpuUsage": 12.5, "memoryUsed": 1, "memoryTotal": 2, "memoryFree": 1, "swapUsed": 0,
+                "swapTotal": 8, "networkRx": 3, "networkTx": 4, "diskRead": 5, "diskWrite": 6,
+                "diskUsage": 7, "diskTotal": 9, "diskFree": 2,
+            }),
+            OpCode::WindowChanged => json!({ "title": "foot" }),
+            OpCode::Workspace => json!({
+                "type": "SpecialShown", "id": -98, "name": "special:magic", "monitor": "DP-1",
+            }),
+            OpCode::SpotifyEvent => json!({
+                "type": "Playing",
+                "trackInfo": {
+                    "title": "Song", "artist": null, "album": "Album", "status": "Playing",
+                    "position": 12, "duration": 180, "volume": "0.5", "artworkUrl": null,
+                },
+            }),
+            OpCode::VolumeEvent => json!({ "volume": 40 }),
+            OpCode::Reply => json!({
+                "id": "1", "ok": false,
+                "error": { "kind": "commandFailed", "message": "pactl failed: gone" },
+            }),
+            OpCode::Health => json!({
+                "source": "spotify", "status": { "state": "restarting", "retryInMs": 2000 },
+                "restarts": 3,
+            }),
+            OpCode::Hello => json!({ "version": 1, "sources": ["sysinfo", "hyprland"] }),
+            OpCode::Custom => json!({ "source": "weather", "value": { "temp": 21 } }),
+            OpCode::WorkspaceState => json!({
+                "workspaces": [{ "id": 1, "name": "1", "monitor": "DP-1", "windows": 2 }],
+                "monitors": [{
+                    "name": "DP-1", "activeWorkspace": 1, "specialWorkspace": null, "focused": true,
+                }],
+                "focusedWindow": { "address": "0xa1", "class": "foot", "title": "~", "workspace": 1 },
+            }),
+            OpCode::Hyprland => json!({
+                "event": "movewindowv2", "address": "0x5a1b", "workspaceId": 3, "workspace": "3",
+            }),
+        }
+    }
+
+    #[test]
+    fn payloads_survive_a_round_trip() {
+        for op in OpCode::ALL {
+            let data = sample(op);
+            let envelope = json!({
+                "op": op, "version": 1, "seq": 7, "time": 1_700_000_000_000u64, "mono": 42,
+                "data": data,
+            });
+
+            let payload = Payload::<Event>::parse(&envelope.to_string()).unwrap();
+
+            assert_eq!(payload.data.op(), op);
+            assert_eq!(payload.op, u16::from(op));
+            assert_eq!((payload.meta.seq, payload.meta.mono), (7, 42));
+            assert_eq!(
+                serde_json::to_value(&payload.data).unwrap(),
+                data,
+                "{:?}",
+                op
+            );
+            assert_eq!(
+                serde_json::to_value(&payload).unwrap(),
+                envelope,
+                "{:?}",
+                op
+            );
+        }
+    }
+
+    #[test]
+    fn opcodes_survive_a_round_trip() {
+        for op in OpCode::ALL {
+            assert_eq!(OpCode::try_from(u16::from(op)).unwrap(), op);
+        }
+
+        let unknown = OpCode::ALL.len() as u16;
+        assert!(OpCode::try_from(unknown).is_err());
+    }
+}
@@ -0,0 +1,374 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use std::{
+    collections::{BTreeMap, HashMap},
+    env,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    io::{AsyncBufReadExt, BufReader, Lines},
+    net::{UnixListener, UnixStream, unix::OwnedReadHalf},
+    task::JoinSet,
+};
+use ts_rs::TS;
+
+use crate::{
+    command::{CommandReply, CommandRequest},
+    err::{BackendError, ErrorPayload},
+    payload::{Hello, Meta, OpCode, PROTOCOL_VERSION, Payload, PayloadData, Retain},
+    sources::Sources,
+    writer::{self, Outbox},
+};
+
+#[derive(Debug, Serialize, Deserialize, TS, JsonSchema)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ClientMessage {
+    // Must be the first line a client sends, `None` means every opcode
+    Subscribe { ops: Option<Vec<OpCode>> },
+    Command(CommandRequest),
+}
+
+struct State {
+    started: Instant,
+    seqs: HashMap<OpCode, u64>,
+    subscribers: HashMap<OpCode, usize>,
+    // Snapshots keep the metadata they were first sent with
+    cache: BTreeMap<(OpCode, String), (Meta, Arc<RawValue>)>,
+    clients: Vec<(Arc<Outbox>, Vec<OpCode>)>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            seqs: HashMap::new(),
+            subscribers: HashMap::new(),
+            cache: BTreeMap::new(),
+            clients: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Emitter {
+    state: Arc<Mutex<State>>,
+}
+
+impl Emitter {
+    pub fn emit<T: PayloadData>(&self, data: T) {
+        let op = data.op();
+        let retain = data.retain();
+
+        // Cache and outboxes are updated under one lock, so attaching clients never see a gap
+        let mut state = self.state.lock().unwrap();
+
+        if !state.is_wanted(op) {
+            return;
+        }
+
+        let value: Arc<RawValue> = match serde_json::value::to_raw_value(&data) {
+            Ok(value) => value.into(),
+            Err(e) => {
+                eprintln!("Failed to serialize payload: {}", e);
+                return;
+            }
+        };
+
+        let meta = state.next_meta(op);
+        let line: Arc<str> = envelope(op, meta, false, &*value).into();
+
+        match retain {
+            Retain::Latest => {
+                state.cache.insert((op, String::new()), (meta, value));
+            }
+            Retain::Entry(key) => {
+                state.cache.insert((op, key), (meta, value));
+            }
+            Retain::Remove(key) => {
+                state.cache.remove(&(op, key));
+            }
+            Retain::Nothing => {}
+        }
+
+        for (outbox, ops) in &state.clients {
+            if ops.contains(&op) {
+                outbox.push(op, line.clone());
+            }
+        }
+    }
+
+    // Reports a failure the module recovers from on its own
+    pub fn error(&self, module: &str, error: BackendError) {
+        self.emit(ErrorPayload {
+            module: module.to_string(),
+            error,
+            retrying: true,
+        });
+    }
+
+    pub fn is_wanted(&self, op: OpCode) -> bool {
+        self.state.lock().unwrap().is_wanted(op)
+    }
+
+    pub fn add_subscribers(&self, ops: &[OpCode]) {
+        let mut state = self.state.lock().unwrap();
+
+        for op in ops {
+            *state.subscribers.entry(*op).or_default() += 1;
+        }
+    }
+
+    pub fn remove_subscribers(&self, ops: &[OpCode]) {
+        let mut state = self.state.lock().unwrap();
+
+        for op in ops {
+            if let Some(count) = state.subscribers.get_mut(op) {
+                *count = count.saturating_sub(1);
+            }
+        }
+    }
+
+    // Called when a source stops, what it reported can't be trusted anymore. With a key only that entry
+    // goes, the others under the same opcodes belong to other sources
+    pub fn forget(&self, ops: &[OpCode], key: Option<&str>) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .cache
+            .retain(|(op, cached), _| !ops.contains(op) || key.is_some_and(|key| key != cached));
+    }
+
+    // Returns an outbox that starts with a hello and the snapshot for `ops`, followed by everything after it
+    fn attach(&self, ops: &[OpCode], sources: Vec<String>) -> Arc<Outbox> {
+        let mut state = self.state.lock().unwrap();
+        let outbox = Arc::new(Outbox::default());
+
+        // Sent regardless of the subscription, clients need it to check compatibility
+        let hello = Hello {
+            version: PROTOCOL_VERSION,
+            sources,
+        };
+        let meta = state.next_meta(OpCode::Hello);
+        outbox.push(
+            OpCode::Hello,
+            envelope(OpCode::Hello, meta, false, &hello).into(),
+        );
+
+        for ((op, _), (meta, value)) in &state.cache {
+            if ops.contains(op) {
+                outbox.push(*op, envelope(*op, *meta, true, &**value).into());
+            }
+        }
+
+        state.clients.push((outbox.clone(), ops.to_vec()));
+
+        outbox
+    }
+
+    // Every client's writer finishes once what is queued for it was sent
+    fn close(&self) {
+        for (outbox, _) in &self.state.lock().unwrap().clients {
+            outbox.close();
+        }
+    }
+
+    fn detach(&self, outbox: &Arc<Outbox>) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .clients
+            .retain(|(client, _)| !Arc::ptr_eq(client, outbox));
+    }
+
+    // Replies go to the requesting client only, but are numbered like everything else
+    fn reply(&self, outbox: &Outbox, reply: CommandReply) {
+        let meta = self.state.lock().unwrap().next_meta(OpCode::Reply);
+        outbox.push(
+            OpCode::Reply,
+            envelope(OpCode::Reply, meta, false, &reply).into(),
+        );
+    }
+}
+
+impl State {
+    fn is_wanted(&self, op: OpCode) -> bool {
+        self.subscribers.get(&op).is_some_and(|count| *count > 0)
+    }
+
+    fn next_meta(&mut self, op: OpCode) -> Meta {
+        let seq = self.seqs.entry(op).or_default();
+        *seq += 1;
+
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64);
+
+        Meta {
+            version: PROTOCOL_VERSION,
+            seq: *seq,
+            time,
+            mono: self.started.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+fn envelope<T: Serialize + ?Sized>(op: OpCode, meta: Meta, snapshot: bool, data: &T) -> String {
+    let payload = Payload {
+        op: op.into(),
+        meta,
+        snapshot,
+        data,
+    };
+
+    // Payloads only hold plain data and strings, so this can't fail
+    serde_json::to_string(&payload).expect("Failed to serialize payload")
+}
+
+// A simulated hub listens elsewhere, so it never stands in for the real one
+pub fn socket_path(simulate: bool) -> Result<PathBuf, BackendError> {
+    let runtime_dir = env::var("XDG_RUNTIME_DIR").map_err(|_| BackendError::XdgRuntimeDirNotSet)?;
+    let name = if simulate {
+        "simulate.sock"
+    } else {
+        "backend.sock"
+    };
+
+    let path = PathBuf::from(runtime_dir).join("skadi").join(name);
+
+    Ok(path)
+}
+
+pub struct Hub {
+    listener: UnixListener,
+    path: PathBuf,
+    emitter: Emitter,
+    clients: JoinSet<()>,
+}
+
+impl Hub {
+    pub async fn bind(simulate: bool) -> Result<Self, BackendError> {
+        let path = socket_path(simulate)?;
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        if path.exists() {
+            // A socket file nobody answers on is left over from a crashed hub
+            if UnixStream::connect(&path).await.is_ok() {
+                return Err(BackendError::HubAlreadyRunning(path.display().to_string()));
+            }
+
+            tokio::fs::remove_file(&path).await?;
+        }
+
+        let listener = UnixListener::bind(&path)?;
+
+        Ok(Self {
+            listener,
+            path,
+            emitter: Emitter::default(),
+            clients: JoinSet::new(),
+        })
+    }
+
+    pub fn emitter(&self) -> Emitter {
+        self.emitter.clone()
+    }
+
+    pub async fn serve(&mut self, sources: Arc<Sources>) -> Result<(), BackendError> {
+        loop {
+            let (stream, _) = self.listener.accept().await?;
+
+            // Clients that left are collected as new ones arrive
+            while self.clients.try_join_next().is_some() {}
+
+            self.clients
+                .spawn(serve_client(stream, self.emitter.clone(), sources.clone()));
+        }
+    }
+
+    // Stops every source, which kills the programs they run, then waits for each client to be sent
+    // what is still queued for it, including the sources stopping
+    pub async fn shutdown(mut self, sources: &Sources) {
+        // Gone first, so a client starting meanwhile brings up a new hub instead of reaching this one
+        if let Err(e) = tokio::fs::remove_file(&self.path).await {
+            eprintln!("Failed to remove {}: {}", self.path.display(), e);
+        }
+
+        sources.shutdown().await;
+        self.emitter.close();
+
+        while self.clients.join_next().await.is_some() {}
+    }
+}
+
+async fn serve_client(stream: UnixStream, emitter: Emitter, sources: Arc<Sources>) {
+    let (read, write) = stream.into_split();
+    let mut lines = BufReader::new(read).lines();
+
+    let Ok(Some(line)) = lines.next_line().await else {
+        return;
+    };
+
+    let ops = match serde_json::from_str(&line) {
+        Ok(ClientMessage::Subscribe { ops }) => ops.unwrap_or_else(|| OpCode::ALL.to_vec()),
+        Ok(_) => {
+            eprintln!("Client sent a message before subscribing");
+            return;
+        }
+        Err(e) => {
+            eprintln!("Invalid subscribe message: {}", e);
+            return;
+        }
+    };
+
+    // Attach before any source starts, so its first events reach us
+    let outbox = emitter.attach(&ops, sources.names());
+    let _subscription = sources.subscribe(ops);
+
+    // Either side ending means the client is gone
+    tokio::select! {
+        _ = writer::run(outbox.clone(), write) => {}
+        _ = read_messages(lines, &emitter, &sources, outbox.clone()) => {}
+    }
+
+    emitter.detach(&outbox);
+}
+
+async fn read_messages(
+    mut lines: Lines<BufReader<OwnedReadHalf>>,
+    emitter: &Emitter,
+    sources: &Arc<Sources>,
+    outbox: Arc<Outbox>,
+) {
+    while let Ok(Some(line)) = lines.next_line().await {
+        handle_message(&line, emitter.clone(), sources.clone(), outbox.clone());
+    }
+}
+
+// Commands run concurrently, their replies go back to the requesting client only
+fn handle_message(line: &str, emitter: Emitter, sources: Arc<Sources>, outbox: Arc<Outbox>) {
+    let request = match serde_json::from_str(line) {
+        Ok(ClientMessage::Command(request)) => request,
+        Ok(ClientMessage::Subscribe { .. }) => {
+            let error = BackendError::InvalidMessage("Already subscribed".to_string());
+            emitter.reply(&outbox, CommandReply::new(None, Err(error)));
+            return;
+        }
+        Err(e) => {
+            let error = BackendError::InvalidMessage(e.to_string());
+            emitter.reply(&outbox, CommandReply::new(None, Err(error)));
+            return;
+        }
+    };
+
+    tokio::spawn(async move {
+        let CommandRequest { id, command } = request;
+        let result = sources.execute(command).await;
+
+        // The client may be gone by the time a slow command finishes, then nobody drains the outbox
+        emitter.reply(&outbox, CommandReply::new(Some(id), result));
+    });
+}
@@ -0,0 +1,185 @@
+use serde::Deserialize;
+
+#[cfg(feature = "hyprland")]
+use {
+    crate::{err::BackendError, hyprland},
+    serde::de::DeserializeOwned,
+    std::path::PathBuf,
+    tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::UnixStream,
+    },
+};
+
+// What Hyprland answers `dispatch` and `keyword` with when they worked, anything else is an error message
+#[cfg(feature = "hyprland")]
+const OK: &str = "ok";
+
+// Workspaces and clients refer to each other by both
+#[derive(Debug, Clone, Deserialize)]
+pub struct WorkspaceRef {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Workspace {
+    pub id: i32,
+    pub name: String,
+    pub monitor: String,
+    #[serde(rename = "monitorID")]
+    pub monitor_id: i32,
+    pub windows: u32,
+    #[serde(rename = "hasfullscreen")]
+    pub has_fullscreen: bool,
+    #[serde(rename = "lastwindow")]
+    pub last_window: String,
+    #[serde(rename = "lastwindowtitle")]
+    pub last_window_title: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Client {
+    pub address: String,
+    pub mapped: bool,
+    pub hidden: bool,
+    pub at: [i32; 2],
+    pub size: [i32; 2],
+    pub workspace: WorkspaceRef,
+    pub floating: bool,
+    // Id of the monitor, not its name
+    pub monitor: i32,
+    pub class: String,
+    pub title: String,
+    pub initial_class: String,
+    pub initial_title: String,
+    pub pid: i32,
+    pub xwayland: bool,
+    pub pinned: bool,
+    // 0 for none, then maximized, fullscreen and both
+    pub fullscreen: u8,
+    #[serde(rename = "focusHistoryID")]
+    pub focus_history_id: i32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Monitor {
+    pub id: i32,
+    pub name: String,
+    pub description: String,
+    pub width: u32,
+    pub height: u32,
+    pub refresh_rate: f64,
+    pub x: i32,
+    pub y: i32,
+    pub active_workspace: WorkspaceRef,
+    // Id 0 while no special workspace is shown on it
+    pub special_workspace: WorkspaceRef,
+    pub scale: f64,
+    pub focused: bool,
+    // Only reported by newer versions
+    #[serde(default)]
+    pub disabled: bool,
+}
+
+// Talks to Hyprland's request socket, `.socket.sock`, the way `hyprctl` does without spawning it.
+// Every request is a connection of its own, Hyprland closes it after replying
+#[cfg(feature = "hyprland")]
+pub struct Hyprctl {
+    path: PathBuf,
+}
+
+#[cfg(feature = "hyprland")]
+impl Hyprctl {
+    pub fn new() -> Result<Self, BackendError> {
+        Ok(Self::at(hyprland::socket_path(".socket.sock")?))
+    }
+
+    pub fn at(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub async fn workspaces(&self) -> Result<Vec<Workspace>, BackendError> {
+        self.query("workspaces").await
+    }
+
+    pub async fn active_workspace(&self) -> Result<Workspace, BackendError> {
+        self.query("activeworkspace").await
+    }
+
+    pub async fn clients(&self) -> Result<Vec<Client>, BackendError> {
+        self.query("clients").await
+    }
+
+    pub async fn monitors(&self) -> Result<Vec<Monitor>, BackendError> {
+        self.query("monitors").await
+    }
+
+    // `None` while no window has focus, which Hyprland answers with an empty object
+    pub async fn active_window(&self) -> Result<Option<Client>, BackendError> {
+        let reply = self.request("j/activewindow").await?;
+
+        if serde_json::from_str::<serde_json::Map<_, _>>(&reply).is_ok_and(|map| map.is_empty()) {
+            return Ok(None);
+        }
+
+        parse("j/activewindow", &reply).map(Some)
+    }
+
+    // E.g. `dispatch("workspace", "3")`
+    pub async fn dispatch(&self, dispatcher: &str, args: &str) -> Result<(), BackendError> {
+        self.command(&format!("/dispatch {} {}", dispatcher, args))
+            .await
+    }
+
+    // Sets a config value until the next reload, e.g. `keyword("general:gaps_in", "5")`
+    pub async fn keyword(&self, keyword: &str, value: &str) -> Result<(), BackendError> {
+        self.command(&format!("/keyword {} {}", keyword, value))
+            .await
+    }
+
+    async fn query<T: DeserializeOwned>(&self, name: &str) -> Result<T, BackendError> {
+        let request = format!("j/{}", name);
+        let reply = self.request(&request).await?;
+
+        parse(&request, &reply)
+    }
+
+    async fn command(&self, request: &str) -> Result<(), BackendError> {
+        let reply = self.request(request).await?;
+
+        if reply.trim() != OK {
+            let request = request.trim_start_matches('/').to_string();
+            return Err(BackendError::HyprlandRequest(
+                request,
+                reply.trim().to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    async fn request(&self, request: &str) -> Result<String, BackendError> {
+        let mut stream = UnixStream::connect(&self.path).await?;
+        stream.write_all(request.as_bytes()).await?;
+
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply).await?;
+
+        Ok(reply)
+    }
+}
+
+#[cfg(feature = "hyprland")]
+fn parse<T: DeserializeOwned>(request: &str, reply: &str) -> Result<T, BackendError> {
+    // Anything that isn't JSON is Hyprland explaining why it refused
+    serde_json::from_str(reply).map_err(|e| {
+        if serde_json::from_str::<serde_json::Value>(reply).is_err() {
+            BackendError::HyprlandRequest(request.to_string(), reply.trim().to_string())
+        } else {
+            BackendError::InvalidOutput(request.to_string(), e.to_string())
+        }
+    })
+}
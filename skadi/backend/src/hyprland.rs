@@ -1,18 +1,32 @@
-use serde::Serialize;
-use std::{env, path::PathBuf};
-use tokio::{
-    io::{AsyncBufReadExt, BufReader},
-    net::UnixStream,
-};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::payload::{OpCode, PayloadData, Retain};
 
-use crate::{
-    err::BackendError,
-    payload::{OpCode, Payload, PayloadData},
+#[cfg(feature = "hyprland")]
+use {
+    crate::{
+        command::Command,
+        config::Config,
+        err::BackendError,
+        hub::Emitter,
+        hyprctl::Hyprctl,
+        hyprland_event::HyprlandEvent,
+        sources::{EventSource, SourceFuture},
+    },
+    std::{collections::HashMap, env, mem, path::PathBuf},
+    tokio::{
+        io::{AsyncBufReadExt, BufReader},
+        net::UnixStream,
+    },
 };
 
-#[derive(Debug, Serialize)]
+pub const MODULE: &str = "hyprland";
+
+#[derive(Debug, Serialize, Deserialize, TS, JsonSchema)]
 pub struct WindowChanged {
-    title: String,
+    pub title: String,
 }
 
 impl PayloadData for WindowChanged {
@@ -21,27 +35,133 @@ impl PayloadData for WindowChanged {
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, TS, JsonSchema)]
+#[ts(repr(enum = name))]
 pub enum WorkspaceEventKind {
+    // Became the active workspace of its monitor
     Moved,
     Created,
     Destroyed,
+    // Now on another monitor
+    Reassigned,
+    // Its monitor got focus
+    Focused,
+    // The monitor it was active on was removed
+    MonitorRemoved,
+    Renamed,
+    // A special workspace, the scratchpad, was shown over the active one of its monitor
+    SpecialShown,
+    SpecialHidden,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, TS, JsonSchema)]
 pub struct Workspace {
     #[serde(rename = "type")]
-    kind: WorkspaceEventKind,
-    id: u16,
+    pub kind: WorkspaceEventKind,
+    // Negative for named and special workspaces
+    pub id: i32,
+    // The number for plain workspaces, `special:` followed by the name for special ones
+    pub name: String,
+    // Name of the monitor it is on
+    pub monitor: String,
 }
 
 impl PayloadData for Workspace {
     fn op(&self) -> OpCode {
         OpCode::Workspace
     }
+
+    // The latest change to each workspace and monitor, for a client joining late to catch up on recent
+    // ones. That is not the full set: workspaces from before the hub started were never reported, and a
+    // later change replaces the Created of its workspace. `WorkspaceState` is the one to draw from
+    fn retain(&self) -> Retain {
+        match self.kind {
+            WorkspaceEventKind::Moved => Retain::Entry(format!("active/{}", self.monitor)),
+            WorkspaceEventKind::Created
+            | WorkspaceEventKind::Reassigned
+            | WorkspaceEventKind::Renamed => Retain::Entry(self.id.to_string()),
+            WorkspaceEventKind::Destroyed => Retain::Remove(self.id.to_string()),
+            WorkspaceEventKind::Focused => Retain::Entry("focused".to_string()),
+            WorkspaceEventKind::MonitorRemoved => {
+                Retain::Remove(format!("active/{}", self.monitor))
+            }
+            WorkspaceEventKind::SpecialShown => Retain::Entry(format!("special/{}", self.monitor)),
+            WorkspaceEventKind::SpecialHidden => {
+                Retain::Remove(format!("special/{}", self.monitor))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS, JsonSchema)]
+pub struct WorkspaceInfo {
+    pub id: i32,
+    pub name: String,
+    // Name of the monitor it is on
+    pub monitor: String,
+    pub windows: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MonitorInfo {
+    pub name: String,
+    pub active_workspace: i32,
+    // Shown over the active one
+    pub special_workspace: Option<i32>,
+    pub focused: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS, JsonSchema)]
+pub struct WindowInfo {
+    pub address: String,
+    pub class: String,
+    pub title: String,
+    // Id of the workspace it is on
+    pub workspace: i32,
 }
 
-fn socket_path() -> Result<PathBuf, BackendError> {
+// Everything needed to draw the workspaces from scratch, queried when the event socket connects
+// and again whenever an event changes it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceState {
+    // Ordered by id
+    pub workspaces: Vec<WorkspaceInfo>,
+    pub monitors: Vec<MonitorInfo>,
+    pub focused_window: Option<WindowInfo>,
+}
+
+impl PayloadData for WorkspaceState {
+    fn op(&self) -> OpCode {
+        OpCode::WorkspaceState
+    }
+}
+
+// Events after which the state is queried again, rather than patched from what the event says.
+// Hyprland sends the `v2` variants right after the others, so only one of each is listed. That is the
+// `v2` one where `Workspace` events are made from it, which needs the state from before. Focus and
+// title changes are not among them, `focused_window` is patched from those
+#[cfg(feature = "hyprland")]
+const STATE_CHANGES: [&str; 12] = [
+    "workspacev2",
+    "createworkspacev2",
+    "destroyworkspacev2",
+    "renameworkspace",
+    "moveworkspace",
+    "activespecialv2",
+    "focusedmonv2",
+    "monitoradded",
+    "monitorremoved",
+    "openwindow",
+    "closewindow",
+    "movewindow",
+];
+
+// One of the sockets in the instance's runtime directory, `.socket2.sock` for events
+// and `.socket.sock` for requests
+#[cfg(feature = "hyprland")]
+pub(crate) fn socket_path(socket: &str) -> Result<PathBuf, BackendError> {
     let runtime_dir = env::var("XDG_RUNTIME_DIR").map_err(|_| BackendError::XdgRuntimeDirNotSet)?;
 
     let signature = env::var("HYPRLAND_INSTANCE_SIGNATURE")
@@ -50,14 +170,84 @@ fn socket_path() -> Result<PathBuf, BackendError> {
     let path = PathBuf::from(runtime_dir)
         .join("hypr")
         .join(signature)
-        .join(".socket2.sock");
+        .join(socket);
 
     Ok(path)
 }
 
-pub async fn hyprland_events() -> Result<(), BackendError> {
-    let path = socket_path()?;
+#[cfg(feature = "hyprland")]
+pub struct Hyprland;
+
+#[cfg(feature = "hyprland")]
+impl EventSource for Hyprland {
+    fn from_config(config: &Config) -> Option<Self> {
+        config.hyprland.enabled.then_some(Self)
+    }
+
+    fn name(&self) -> &'static str {
+        MODULE
+    }
+
+    fn ops(&self) -> &'static [OpCode] {
+        &[
+            OpCode::Workspace,
+            OpCode::WindowChanged,
+            OpCode::WorkspaceState,
+            OpCode::Hyprland,
+        ]
+    }
+
+    fn start(&self, emitter: Emitter) -> SourceFuture<'_, Result<(), BackendError>> {
+        Box::pin(hyprland_events(emitter))
+    }
+
+    fn command(&self, command: Command) -> SourceFuture<'_, Result<(), BackendError>> {
+        Box::pin(async move {
+            match command {
+                Command::Workspace { id } => dispatch_workspace(id).await,
+                _ => Err(BackendError::UnsupportedCommand(MODULE.to_string())),
+            }
+        })
+    }
+}
+
+// A negative id would be taken as relative to the current workspace, so named and special ones are
+// looked up and switched to by name
+#[cfg(feature = "hyprland")]
+async fn dispatch_workspace(id: i32) -> Result<(), BackendError> {
+    let hyprctl = Hyprctl::new()?;
+
+    if id > 0 {
+        return hyprctl.dispatch("workspace", &id.to_string()).await;
+    }
+
+    let workspaces = hyprctl.workspaces().await?;
+    let Some(workspace) = workspaces.iter().find(|workspace| workspace.id == id) else {
+        return Err(BackendError::InvalidMessage(format!("No workspace {}", id)));
+    };
+
+    match workspace.name.strip_prefix("special:") {
+        Some(special) => hyprctl.dispatch("togglespecialworkspace", special).await,
+        None => {
+            hyprctl
+                .dispatch("workspace", &format!("name:{}", workspace.name))
+                .await
+        }
+    }
+}
+
+#[cfg(feature = "hyprland")]
+async fn hyprland_events(emitter: Emitter) -> Result<(), BackendError> {
+    let path = socket_path(".socket2.sock")?;
     let stream = UnixStream::connect(path).await?;
+    let hyprctl = Hyprctl::new()?;
+
+    // Queried once connected, so nothing that happens in between is missed
+    let mut state = None;
+    let mut monitors = Monitors::default();
+    // Class and title from `activewindow`, for the `activewindowv2` after it with the address
+    let mut activated = (String::new(), String::new());
+    refresh_state(&hyprctl, &emitter, &mut state, &mut monitors).await;
 
     let reader = BufReader::new(stream);
     let mut lines = reader.lines();
@@ -67,41 +257,307 @@ pub async fn hyprland_events() -> Result<(), BackendError> {
             continue;
         };
 
-        match event {
-            "workspace" | "createworkspace" | "destroyworkspace" => {
-                let event = Workspace {
-                    id: data.parse().unwrap_or(0),
-                    kind: match event {
-                        "workspace" => WorkspaceEventKind::Moved,
-                        "createworkspace" => WorkspaceEventKind::Created,
-                        "destroyworkspace" => WorkspaceEventKind::Destroyed,
-                        _ => continue,
-                    },
-                };
-
-                let payload = Payload::new(event);
-
-                println!(
-                    "{}",
-                    serde_json::to_string(&payload).expect("Failed to serialize event")
-                );
+        let Some(parsed) = HyprlandEvent::parse(event, data) else {
+            let error = BackendError::InvalidOutput("Hyprland".to_string(), line.clone());
+            emitter.error(MODULE, error);
+            continue;
+        };
+
+        let workspace = monitors.update(&parsed);
+        let window = focused_window(&parsed, &mut activated, &monitors, state.as_ref());
+        let title = match &parsed {
+            HyprlandEvent::ActiveWindow { title, .. } => Some(title.clone()),
+            _ => None,
+        };
+        emitter.emit(parsed);
+
+        if let Some(workspace) = workspace {
+            emitter.emit(workspace);
+        }
+
+        if let Some(title) = title {
+            emitter.emit(WindowChanged { title });
+        }
+
+        if let Some(window) = window {
+            focus_window(&emitter, &mut state, window);
+        }
+
+        if STATE_CHANGES.contains(&event) {
+            refresh_state(&hyprctl, &emitter, &mut state, &mut monitors).await;
+        }
+    }
+
+    Err(BackendError::SourceExited(
+        "Hyprland event socket".to_string(),
+    ))
+}
+
+// Emits the state if it differs from the last one sent. A failed query is reported, events keep flowing
+#[cfg(feature = "hyprland")]
+async fn refresh_state(
+    hyprctl: &Hyprctl,
+    emitter: &Emitter,
+    last: &mut Option<WorkspaceState>,
+    monitors: &mut Monitors,
+) {
+    match query_state(hyprctl).await {
+        Ok(state) => {
+            monitors.sync(&state);
+
+            if last.as_ref() != Some(&state) {
+                emitter.emit(state.clone());
+                *last = Some(state);
             }
+        }
+        Err(e) => emitter.error(MODULE, e),
+    }
+}
+
+// The focused window after a focus or title change, `None` if the event leaves it as it is. A window
+// focused on a workspace not known yet is left to the next state queried
+#[cfg(feature = "hyprland")]
+fn focused_window(
+    event: &HyprlandEvent,
+    activated: &mut (String, String),
+    monitors: &Monitors,
+    last: Option<&WorkspaceState>,
+) -> Option<Option<WindowInfo>> {
+    match event {
+        HyprlandEvent::ActiveWindow { class, title } => {
+            *activated = (class.clone(), title.clone());
+            None
+        }
+        HyprlandEvent::ActiveWindowV2 { address: None } => Some(None),
+        HyprlandEvent::ActiveWindowV2 {
+            address: Some(address),
+        } => {
+            let (class, title) = mem::take(activated);
+
+            Some(Some(WindowInfo {
+                address: address.clone(),
+                class,
+                title,
+                workspace: monitors.current()?,
+            }))
+        }
+        HyprlandEvent::WindowTitleV2 { address, title } => {
+            let window = last?.focused_window.as_ref()?;
+
+            (window.address == *address).then(|| {
+                Some(WindowInfo {
+                    title: title.clone(),
+                    ..window.clone()
+                })
+            })
+        }
+        _ => None,
+    }
+}
+
+// Emits the state with another focused window, if that changes it
+#[cfg(feature = "hyprland")]
+fn focus_window(emitter: &Emitter, last: &mut Option<WorkspaceState>, window: Option<WindowInfo>) {
+    if let Some(state) = last
+        && state.focused_window != window
+    {
+        state.focused_window = window;
+        emitter.emit(state.clone());
+    }
+}
+
+#[cfg(feature = "hyprland")]
+async fn query_state(hyprctl: &Hyprctl) -> Result<WorkspaceState, BackendError> {
+    let (mut workspaces, monitors, window) = tokio::try_join!(
+        hyprctl.workspaces(),
+        hyprctl.monitors(),
+        hyprctl.active_window()
+    )?;
+
+    workspaces.sort_by_key(|workspace| workspace.id);
+
+    Ok(WorkspaceState {
+        workspaces: workspaces
+            .into_iter()
+            .map(|workspace| WorkspaceInfo {
+                id: workspace.id,
+                name: workspace.name,
+                monitor: workspace.monitor,
+                windows: workspace.windows,
+            })
+            .collect(),
+        monitors: monitors
+            .into_iter()
+            .map(|monitor| MonitorInfo {
+                name: monitor.name,
+                active_workspace: monitor.active_workspace.id,
+                special_workspace: (monitor.special_workspace.id != 0)
+                    .then_some(monitor.special_workspace.id),
+                focused: monitor.focused,
+            })
+            .collect(),
+        focused_window: window.map(|window| WindowInfo {
+            address: window.address,
+            class: window.class,
+            title: window.title,
+            workspace: window.workspace.id,
+        }),
+    })
+}
+
+// Which monitor every workspace is on and what it is called, to put both in `Workspace` events. Follows
+// the events and is replaced by every state queried in between, which catches what they leave out
+#[cfg(feature = "hyprland")]
+#[derive(Default)]
+struct Monitors {
+    focused: String,
+    // Active workspace of every connected monitor, `None` until one was reported
+    active: HashMap<String, Option<i32>>,
+    // Special workspace shown on a monitor
+    special: HashMap<String, i32>,
+    // Name and monitor by id
+    workspaces: HashMap<i32, (String, String)>,
+}
 
-            "activewindow" => {
-                let event = WindowChanged {
-                    title: data.to_string(),
-                };
-                let payload = Payload::new(event);
+#[cfg(feature = "hyprland")]
+impl Monitors {
+    fn sync(&mut self, state: &WorkspaceState) {
+        self.focused = state
+            .monitors
+            .iter()
+            .find(|monitor| monitor.focused)
+            .map(|monitor| monitor.name.clone())
+            .unwrap_or_default();
 
-                println!(
-                    "{}",
-                    serde_json::to_string(&payload).expect("Failed to serialize event")
-                );
+        self.active = state
+            .monitors
+            .iter()
+            .map(|monitor| (monitor.name.clone(), Some(monitor.active_workspace)))
+            .collect();
+
+        self.special = state
+            .monitors
+            .iter()
+            .filter_map(|monitor| Some((monitor.name.clone(), monitor.special_workspace?)))
+            .collect();
+
+        self.workspaces = state
+            .workspaces
+            .iter()
+            .map(|workspace| {
+                let place = (workspace.name.clone(), workspace.monitor.clone());
+                (workspace.id, place)
+            })
+            .collect();
+    }
+
+    // The `Workspace` event a Hyprland event amounts to. Workspaces are taken from the `v2` events,
+    // which have ids, the others only name them
+    fn update(&mut self, event: &HyprlandEvent) -> Option<Workspace> {
+        let workspace = match event {
+            // Switching to a workspace on another monitor focuses that one, new ones open on the focused one
+            HyprlandEvent::WorkspaceV2 { id, name } => {
+                let monitor = self.monitor_of(*id);
+                self.focused = monitor.clone();
+                self.active.insert(monitor.clone(), Some(*id));
+                self.place(*id, name, &monitor);
+                self.workspace(WorkspaceEventKind::Moved, *id, &monitor)
+            }
+            HyprlandEvent::CreateWorkspaceV2 { id, name } => {
+                let monitor = self.monitor_of(*id);
+                self.place(*id, name, &monitor);
+                self.workspace(WorkspaceEventKind::Created, *id, &monitor)
+            }
+            HyprlandEvent::DestroyWorkspaceV2 { id, .. } => {
+                let monitor = self.monitor_of(*id);
+                let workspace = self.workspace(WorkspaceEventKind::Destroyed, *id, &monitor);
+                self.workspaces.remove(id);
+                workspace
+            }
+            HyprlandEvent::MoveWorkspaceV2 { id, name, monitor } => {
+                self.place(*id, name, monitor);
+                self.workspace(WorkspaceEventKind::Reassigned, *id, monitor)
+            }
+            HyprlandEvent::RenameWorkspace { id, name } => {
+                let monitor = self.monitor_of(*id);
+                self.place(*id, name, &monitor);
+                self.workspace(WorkspaceEventKind::Renamed, *id, &monitor)
+            }
+            HyprlandEvent::FocusedMonV2 {
+                monitor,
+                workspace_id,
+            } => {
+                self.focused = monitor.clone();
+                self.active.insert(monitor.clone(), Some(*workspace_id));
+                self.workspace(WorkspaceEventKind::Focused, *workspace_id, monitor)
+            }
+            // A special workspace is brought to the monitor it is shown on
+            HyprlandEvent::ActiveSpecialV2 {
+                id: Some(id),
+                name,
+                monitor,
+            } => {
+                if let Some(name) = name {
+                    self.place(*id, name, monitor);
+                }
+
+                self.special.insert(monitor.clone(), *id);
+                self.workspace(WorkspaceEventKind::SpecialShown, *id, monitor)
+            }
+            HyprlandEvent::ActiveSpecialV2 {
+                id: None, monitor, ..
+            } => {
+                let id = self.special.remove(monitor)?;
+                self.workspace(WorkspaceEventKind::SpecialHidden, id, monitor)
+            }
+            HyprlandEvent::MonitorAdded { name } => {
+                self.active.entry(name.clone()).or_default();
+                return None;
             }
+            HyprlandEvent::MonitorRemoved { name } => {
+                self.special.remove(name);
 
-            _ => {}
+                let id = self.active.remove(name)??;
+                self.workspace(WorkspaceEventKind::MonitorRemoved, id, name)
+            }
+            _ => return None,
+        };
+
+        Some(workspace)
+    }
+
+    // Where a window that just got focus is, the special workspace shown on the focused monitor or
+    // else its active one
+    fn current(&self) -> Option<i32> {
+        self.special
+            .get(&self.focused)
+            .copied()
+            .or_else(|| *self.active.get(&self.focused)?)
+    }
+
+    fn place(&mut self, id: i32, name: &str, monitor: &str) {
+        self.workspaces
+            .insert(id, (name.to_string(), monitor.to_string()));
+    }
+
+    fn workspace(&self, kind: WorkspaceEventKind, id: i32, monitor: &str) -> Workspace {
+        let name = match self.workspaces.get(&id) {
+            Some((name, _)) => name.clone(),
+            None => id.to_string(),
+        };
+
+        Workspace {
+            kind,
+            id,
+            name,
+            monitor: monitor.to_string(),
         }
     }
 
-    Ok(())
+    fn monitor_of(&self, id: i32) -> String {
+        match self.workspaces.get(&id) {
+            Some((_, monitor)) => monitor.clone(),
+            None => self.focused.clone(),
+        }
+    }
 }
@@ -0,0 +1,463 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::payload::{OpCode, PayloadData, Retain};
+
+// Every line of Hyprland's event socket, tagged with the event's own name. Hyprland sends the `v2` variants
+// alongside the older ones, which name workspaces where `v2` has ids. Window addresses are written like
+// `j/clients` reports them, with `0x`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS, JsonSchema)]
+#[serde(
+    tag = "event",
+    rename_all = "lowercase",
+    rename_all_fields = "camelCase"
+)]
+pub enum HyprlandEvent {
+    Workspace {
+        name: String,
+    },
+    WorkspaceV2 {
+        id: i32,
+        name: String,
+    },
+    FocusedMon {
+        monitor: String,
+        workspace: String,
+    },
+    FocusedMonV2 {
+        monitor: String,
+        workspace_id: i32,
+    },
+    ActiveWindow {
+        class: String,
+        title: String,
+    },
+    // `None` once no window has focus
+    ActiveWindowV2 {
+        address: Option<String>,
+    },
+    Fullscreen {
+        enabled: bool,
+    },
+    MonitorRemoved {
+        name: String,
+    },
+    MonitorRemovedV2 {
+        id: i32,
+        name: String,
+        description: String,
+    },
+    MonitorAdded {
+        name: String,
+    },
+    MonitorAddedV2 {
+        id: i32,
+        name: String,
+        description: String,
+    },
+    CreateWorkspace {
+        name: String,
+    },
+    CreateWorkspaceV2 {
+        id: i32,
+        name: String,
+    },
+    DestroyWorkspace {
+        name: String,
+    },
+    DestroyWorkspaceV2 {
+        id: i32,
+        name: String,
+    },
+    MoveWorkspace {
+        name: String,
+        monitor: String,
+    },
+    MoveWorkspaceV2 {
+        id: i32,
+        name: String,
+        monitor: String,
+    },
+    RenameWorkspace {
+        id: i32,
+        name: String,
+    },
+    // `None` once the special workspace on the monitor was closed
+    ActiveSpecial {
+        name: Option<String>,
+        monitor: String,
+    },
+    ActiveSpecialV2 {
+        id: Option<i32>,
+        name: Option<String>,
+        monitor: String,
+    },
+    ActiveLayout {
+        keyboard: String,
+        layout: String,
+    },
+    OpenWindow {
+        address: String,
+        workspace: String,
+        class: String,
+        title: String,
+    },
+    CloseWindow {
+        address: String,
+    },
+    MoveWindow {
+        address: String,
+        workspace: String,
+    },
+    MoveWindowV2 {
+        address: String,
+        workspace_id: i32,
+        workspace: String,
+    },
+    OpenLayer {
+        namespace: String,
+    },
+    CloseLayer {
+        namespace: String,
+    },
+    // `None` when back to the default one
+    Submap {
+        name: Option<String>,
+    },
+    ChangeFloatingMode {
+        address: String,
+        floating: bool,
+    },
+    Urgent {
+        address: String,
+    },
+    Minimized {
+        address: String,
+        minimized: bool,
+    },
+    Screencast {
+        active: bool,
+        // A single window is shared rather than a whole monitor
+        window: bool,
+    },
+    WindowTitle {
+        address: String,
+    },
+    WindowTitleV2 {
+        address: String,
+        title: String,
+    },
+    ToggleGroup {
+        open: bool,
+        addresses: Vec<String>,
+    },
+    MoveIntoGroup {
+        address: String,
+    },
+    MoveOutOfGroup {
+        address: String,
+    },
+    IgnoreGroupLock {
+        enabled: bool,
+    },
+    LockGroups {
+        enabled: bool,
+    },
+    Pin {
+        address: String,
+        pinned: bool,
+    },
+    // `None` when no window rang it
+    Bell {
+        address: Option<String>,
+    },
+    ConfigReloaded,
+    // Anything newer than this list, passed on as Hyprland sent it
+    Other {
+        name: String,
+        data: String,
+    },
+}
+
+impl PayloadData for HyprlandEvent {
+    fn op(&self) -> OpCode {
+        OpCode::Hyprland
+    }
+
+    // Changes, the state they add up to is kept by `WorkspaceState`
+    fn retain(&self) -> Retain {
+        Retain::Nothing
+    }
+}
+
+impl HyprlandEvent {
+    // Parses the part after `>>`, `None` if it doesn't have the fields the event should have.
+    // Names and titles may contain commas themselves, so they are split off last
+    pub fn parse(name: &str, data: &str) -> Option<Self> {
+        let event = match name {
+            "workspace" => HyprlandEvent::Workspace {
+                name: data.to_string(),
+            },
+            "workspacev2" => {
+                let (id, name) = id_and_name(data)?;
+                HyprlandEvent::WorkspaceV2 { id, name }
+            }
+            "focusedmon" => {
+                let (monitor, workspace) = data.split_once(',')?;
+                HyprlandEvent::FocusedMon {
+                    monitor: monitor.to_string(),
+                    workspace: workspace.to_string(),
+                }
+            }
+            "focusedmonv2" => {
+                let (monitor, workspace_id) = data.rsplit_once(',')?;
+                HyprlandEvent::FocusedMonV2 {
+                    monitor: monitor.to_string(),
+                    workspace_id: workspace_id.parse().ok()?,
+                }
+            }
+            "activewindow" => {
+                let (class, title) = data.split_once(',')?;
+                HyprlandEvent::ActiveWindow {
+                    class: class.to_string(),
+                    title: title.to_string(),
+                }
+            }
+            "activewindowv2" => HyprlandEvent::ActiveWindowV2 {
+                address: optional_address(data),
+            },
+            "fullscreen" => HyprlandEvent::Fullscreen {
+                enabled: flag(data)?,
+            },
+            "monitorremoved" => HyprlandEvent::MonitorRemoved {
+                name: data.to_string(),
+            },
+            "monitorremovedv2" => {
+                let (id, name, description) = monitor(data)?;
+                HyprlandEvent::MonitorRemovedV2 {
+                    id,
+                    name,
+                    description,
+                }
+            }
+            "monitoradded" => HyprlandEvent::MonitorAdded {
+                name: data.to_string(),
+            },
+            "monitoraddedv2" => {
+                let (id, name, description) = monitor(data)?;
+                HyprlandEvent::MonitorAddedV2 {
+                    id,
+                    name,
+                    description,
+                }
+            }
+            "createworkspace" => HyprlandEvent::CreateWorkspace {
+                name: data.to_string(),
+            },
+            "createworkspacev2" => {
+                let (id, name) = id_and_name(data)?;
+                HyprlandEvent::CreateWorkspaceV2 { id, name }
+            }
+            "destroyworkspace" => HyprlandEvent::DestroyWorkspace {
+                name: data.to_string(),
+            },
+            "destroyworkspacev2" => {
+                let (id, name) = id_and_name(data)?;
+                HyprlandEvent::DestroyWorkspaceV2 { id, name }
+            }
+            "moveworkspace" => {
+                let (name, monitor) = data.rsplit_once(',')?;
+                HyprlandEvent::MoveWorkspace {
+                    name: name.to_string(),
+                    monitor: monitor.to_string(),
+                }
+            }
+            "moveworkspacev2" => {
+                let (rest, monitor) = data.rsplit_once(',')?;
+                let (id, name) = id_and_name(rest)?;
+                HyprlandEvent::MoveWorkspaceV2 {
+                    id,
+                    name,
+                    monitor: monitor.to_string(),
+                }
+            }
+            "renameworkspace" => {
+                let (id, name) = id_and_name(data)?;
+                HyprlandEvent::RenameWorkspace { id, name }
+            }
+            "activespecial" => {
+                let (name, monitor) = data.rsplit_once(',')?;
+                HyprlandEvent::ActiveSpecial {
+                    name: non_empty(name),
+                    monitor: monitor.to_string(),
+                }
+            }
+            "activespecialv2" => {
+                let (rest, monitor) = data.rsplit_once(',')?;
+                let (id, name) = rest.split_once(',')?;
+                HyprlandEvent::ActiveSpecialV2 {
+                    id: match id {
+                        "" => None,
+                        id => Some(id.parse().ok()?),
+                    },
+                    name: non_empty(name),
+                    monitor: monitor.to_string(),
+                }
+            }
+            "activelayout" => {
+                let (keyboard, layout) = data.split_once(',')?;
+                HyprlandEvent::ActiveLayout {
+                    keyboard: keyboard.to_string(),
+                    layout: layout.to_string(),
+                }
+            }
+            "openwindow" => {
+                let [window, workspace, class, title] = split(data)?;
+                HyprlandEvent::OpenWindow {
+                    address: address(window),
+                    workspace: workspace.to_string(),
+                    class: class.to_string(),
+                    title: title.to_string(),
+                }
+            }
+            "closewindow" => HyprlandEvent::CloseWindow {
+                address: address(data),
+            },
+            "movewindow" => {
+                let (window, workspace) = data.split_once(',')?;
+                HyprlandEvent::MoveWindow {
+                    address: address(window),
+                    workspace: workspace.to_string(),
+                }
+            }
+            "movewindowv2" => {
+                let [window, workspace_id, workspace] = split(data)?;
+                HyprlandEvent::MoveWindowV2 {
+                    address: address(window),
+                    workspace_id: workspace_id.parse().ok()?,
+                    workspace: workspace.to_string(),
+                }
+            }
+            "openlayer" => HyprlandEvent::OpenLayer {
+                namespace: data.to_string(),
+            },
+            "closelayer" => HyprlandEvent::CloseLayer {
+                namespace: data.to_string(),
+            },
+            "submap" => HyprlandEvent::Submap {
+                name: non_empty(data),
+            },
+            "changefloatingmode" => {
+                let (window, floating) = data.split_once(',')?;
+                HyprlandEvent::ChangeFloatingMode {
+                    address: address(window),
+                    floating: flag(floating)?,
+                }
+            }
+            "urgent" => HyprlandEvent::Urgent {
+                address: address(data),
+            },
+            "minimized" => {
+                let (window, minimized) = data.split_once(',')?;
+                HyprlandEvent::Minimized {
+                    address: address(window),
+                    minimized: flag(minimized)?,
+                }
+            }
+            "screencast" => {
+                let (active, owner) = data.split_once(',')?;
+                HyprlandEvent::Screencast {
+                    active: flag(active)?,
+                    window: flag(owner)?,
+                }
+            }
+            "windowtitle" => HyprlandEvent::WindowTitle {
+                address: address(data),
+            },
+            "windowtitlev2" => {
+                let (window, title) = data.split_once(',')?;
+                HyprlandEvent::WindowTitleV2 {
+                    address: address(window),
+                    title: title.to_string(),
+                }
+            }
+            "togglegroup" => {
+                let (open, windows) = data.split_once(',')?;
+                HyprlandEvent::ToggleGroup {
+                    open: flag(open)?,
+                    addresses: windows.split(',').map(address).collect(),
+                }
+            }
+            "moveintogroup" => HyprlandEvent::MoveIntoGroup {
+                address: address(data),
+            },
+            "moveoutofgroup" => HyprlandEvent::MoveOutOfGroup {
+                address: address(data),
+            },
+            "ignoregrouplock" => HyprlandEvent::IgnoreGroupLock {
+                enabled: flag(data)?,
+            },
+            "lockgroups" => HyprlandEvent::LockGroups {
+                enabled: flag(data)?,
+            },
+            "pin" => {
+                let (window, pinned) = data.split_once(',')?;
+                HyprlandEvent::Pin {
+                    address: address(window),
+                    pinned: flag(pinned)?,
+                }
+            }
+            "bell" => HyprlandEvent::Bell {
+                address: optional_address(data),
+            },
+            "configreloaded" => HyprlandEvent::ConfigReloaded,
+            _ => HyprlandEvent::Other {
+                name: name.to_string(),
+                data: data.to_string(),
+            },
+        };
+
+        Some(event)
+    }
+}
+
+fn id_and_name(data: &str) -> Option<(i32, String)> {
+    let (id, name) = data.split_once(',')?;
+    Some((id.parse().ok()?, name.to_string()))
+}
+
+// `ID,NAME,DESCRIPTION`, where only the description may contain commas
+fn monitor(data: &str) -> Option<(i32, String, String)> {
+    let [id, name, description] = split(data)?;
+    Some((id.parse().ok()?, name.to_string(), description.to_string()))
+}
+
+// Exactly `N` fields, the last one keeping any further commas
+fn split<const N: usize>(data: &str) -> Option<[&str; N]> {
+    let fields: Vec<&str> = data.splitn(N, ',').collect();
+    fields.try_into().ok()
+}
+
+fn flag(data: &str) -> Option<bool> {
+    match data {
+        "1" => Some(true),
+        "0" => Some(false),
+        _ => None,
+    }
+}
+
+fn address(data: &str) -> String {
+    format!("0x{}", data.trim_start_matches("0x"))
+}
+
+fn optional_address(data: &str) -> Option<String> {
+    match data.trim_matches(',') {
+        "" => None,
+        window => Some(address(window)),
+    }
+}
+
+fn non_empty(data: &str) -> Option<String> {
+    (!data.is_empty()).then(|| data.to_string())
+}
@@ -0,0 +1,19 @@
+pub mod codegen;
+pub mod command;
+pub mod config;
+pub mod custom;
+pub mod err;
+pub mod event;
+pub mod hub;
+pub mod hyprctl;
+pub mod hyprland;
+pub mod hyprland_event;
+pub mod payload;
+pub mod schema;
+pub mod simulate;
+pub mod sources;
+pub mod spotify;
+pub mod stream;
+pub mod sysinfo;
+pub mod volume;
+mod writer;
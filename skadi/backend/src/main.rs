@@ -1,164 +1,312 @@
-mod err;
-mod hyprland;
-mod payload;
-mod spotify;
-mod sysinfo;
-mod volume;
-
-use crate::err::BackendError;
-use crate::hyprland::hyprland_events;
-use crate::payload::Payload;
-use crate::spotify::{PlayerctlListener, SpotifyEvent, SpotifyEventKind};
-use crate::sysinfo::poll_sysinfo;
-use crate::volume::monitor_volume_changes;
-use clap::Parser;
-use std::time::Duration;
-
-#[derive(Debug, Parser)]
+mod client;
+mod recording;
+
+use backend::config::{self, Config};
+use backend::err::BackendError;
+use backend::hub::{Emitter, Hub};
+use backend::payload::OpCode;
+use backend::simulate::{self, EdgeCase};
+use backend::sources::{self, Source, Sources};
+use backend::{codegen, schema};
+use clap::{Parser, Subcommand};
+use recording::Speed;
+use std::{path::PathBuf, process::ExitCode, sync::Arc, time::Duration};
+use tokio::signal::unix::{Signal, SignalKind, signal};
+
+// Every integration the hub can run, a new one only has to implement `EventSource` and be listed here
+// behind the feature that compiles it in
+const REGISTRY: &[fn(&Config) -> Vec<Source>] = &[
+    #[cfg(feature = "sysinfo")]
+    sources::register::<backend::sysinfo::Sysinfo>,
+    #[cfg(feature = "hyprland")]
+    sources::register::<backend::hyprland::Hyprland>,
+    #[cfg(feature = "spotify")]
+    sources::register::<backend::spotify::Spotify>,
+    #[cfg(feature = "volume")]
+    sources::register::<backend::volume::Volume>,
+    #[cfg(feature = "custom")]
+    backend::custom::sources,
+];
+
+// Editors save in several steps, reloading waits for them to settle
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(200);
+
+// A client that stopped reading must not keep the hub from exiting
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Parser)]
 pub struct Args {
-    #[arg(long, default_value = "5")]
-    pub sysinfo_poll_interval: u64,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Read module settings from this file instead of `$XDG_CONFIG_HOME/skadi/backend.toml`
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// Overrides `sysinfo.poll_interval`, in seconds
+    #[arg(long)]
+    pub sysinfo_poll_interval: Option<u64>,
+
+    /// Overrides `sysinfo.mount`
+    #[arg(long)]
+    pub mount: Option<PathBuf>,
+
+    /// Overrides `spotify.player`
+    #[arg(long)]
+    pub player: Option<String>,
+
+    /// Overrides `volume.sink`
+    #[arg(long)]
+    pub sink: Option<String>,
+
+    /// Generate fake data for every enabled module instead of reading the system
+    #[arg(long)]
+    pub simulate: bool,
+
+    /// Cases to mix into the simulation, e.g. `--edge-cases long-titles,volume-150`
+    #[arg(long, value_delimiter = ',', requires = "simulate")]
+    pub edge_cases: Vec<EdgeCase>,
+
+    /// Only receive these opcodes, e.g. `--only workspace,volume`
+    #[arg(long, value_delimiter = ',')]
+    pub only: Vec<OpCode>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// Run the shared event hub that every client connects to
+    Serve,
+
+    /// Send a single command request, e.g. `{"id":"1","command":{"type":"volumeMute"}}`
+    Send { request: String },
+
+    /// Print the TypeScript payload types, or write them to a file
+    Codegen {
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+
+    /// Print JSON Schema for the envelope, every payload by opcode and every command
+    Schema {
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+
+    /// Write the payload stream with its timing to a file, until stopped
+    Record { file: PathBuf },
+
+    /// Print a recording on stdout the way the live backend would
+    Replay {
+        file: PathBuf,
+
+        /// Playback rate, e.g. `2x` or `0.5x`
+        #[arg(long, default_value = "1x")]
+        speed: Speed,
+
+        /// Start over at the end instead of exiting
+        #[arg(long = "loop")]
+        looped: bool,
+    },
 }
 
 #[tokio::main]
-async fn main() -> Result<(), BackendError> {
+async fn main() -> Result<ExitCode, BackendError> {
     let args = Args::parse();
 
-    let sysinfo_handle = tokio::spawn(async move {
-        let interval = Duration::from_secs(args.sysinfo_poll_interval);
-        poll_sysinfo(interval).await
-    });
+    match args.command {
+        Some(Command::Serve) => return serve(args).await,
+        Some(Command::Send { ref request }) => client::send(&args, request).await,
+        Some(Command::Codegen { ref out }) => codegen::write(out.as_deref()),
+        Some(Command::Schema { ref out }) => schema::write(out.as_deref()),
+        Some(Command::Record { ref file }) => recording::record(&args, file).await,
+        Some(Command::Replay {
+            ref file,
+            speed,
+            looped,
+        }) => recording::replay(file, speed, looped).await,
+        None => client::run(&args).await,
+    }?;
 
-    let hyprland_handle = tokio::spawn(async move {
-        if let Err(e) = hyprland_events().await {
-            eprintln!("Error in Hyprland events: {}", e);
-        }
-    });
-
-    let spotify_handle = tokio::spawn(async move {
-        let listener = PlayerctlListener::new("spotify");
-
-        if let Ok(track_info) = listener.get_current_track().await {
-            let event = SpotifyEvent {
-                kind: SpotifyEventKind::Request,
-                track_info,
-            };
-            let payload = Payload::new(event);
-
-            println!(
-                "{}",
-                serde_json::to_string(&payload).expect("Failed to serialize Spotify event")
-            );
+    Ok(ExitCode::SUCCESS)
+}
+
+// Runs until stopped by a signal, then exits with 128 plus its number like the shell reports it
+async fn serve(args: Args) -> Result<ExitCode, BackendError> {
+    // Registered before anything is started, so a signal arriving early still shuts down cleanly
+    let mut signals = StopSignals::new()?;
+
+    let mut hub = match Hub::bind(args.simulate).await {
+        Ok(hub) => hub,
+        // Lost the race against another client starting the hub
+        Err(BackendError::HubAlreadyRunning(_)) => return Ok(ExitCode::SUCCESS),
+        Err(e) => return Err(e),
+    };
+
+    eprintln!(
+        "Modules compiled in: {}",
+        sources::compiled_modules().join(", ")
+    );
+
+    let config = load_config(&args)?;
+    let sources = Sources::new(hub.emitter(), enabled_sources(&config, &args));
+
+    tokio::spawn(reload_config(args, hub.emitter(), sources.clone(), config));
+
+    let signal = tokio::select! {
+        result = hub.serve(sources.clone()) => return result.map(|()| ExitCode::FAILURE),
+        signal = signals.next() => signal,
+    };
+
+    eprintln!("Received signal {}, shutting down", signal);
+
+    // A second signal means not waiting any longer
+    tokio::select! {
+        _ = hub.shutdown(&sources) => {}
+        _ = signals.next() => eprintln!("Received another signal, exiting right away"),
+        _ = tokio::time::sleep(SHUTDOWN_TIMEOUT) => eprintln!("Timed out flushing clients"),
+    }
+
+    Ok(ExitCode::from(128 + signal as u8))
+}
+
+// What stops the hub: SIGTERM when the bar reloads, SIGINT from a terminal, SIGHUP when it closes
+struct StopSignals {
+    terminate: Signal,
+    interrupt: Signal,
+    hangup: Signal,
+}
+
+impl StopSignals {
+    fn new() -> Result<Self, BackendError> {
+        Ok(Self {
+            terminate: signal(SignalKind::terminate())?,
+            interrupt: signal(SignalKind::interrupt())?,
+            hangup: signal(SignalKind::hangup())?,
+        })
+    }
+
+    // The number of the next one received
+    async fn next(&mut self) -> i32 {
+        let kind = tokio::select! {
+            _ = self.terminate.recv() => SignalKind::terminate(),
+            _ = self.interrupt.recv() => SignalKind::interrupt(),
+            _ = self.hangup.recv() => SignalKind::hangup(),
+        };
+
+        kind.as_raw_value()
+    }
+}
+
+// Applies edits to the config file while running, a bad edit is reported and the previous config kept
+async fn reload_config(args: Args, emitter: Emitter, sources: Arc<Sources>, mut config: Config) {
+    let Some(path) = args.config.clone().or_else(config::config_path) else {
+        return;
+    };
+
+    let (_watcher, mut changes) = match config::watch(&path) {
+        Ok(watch) => watch,
+        Err(e) => {
+            emitter.error(config::MODULE, e);
+            return;
         }
+    };
+
+    while changes.recv().await.is_some() {
+        tokio::time::sleep(RELOAD_DEBOUNCE).await;
+        while changes.try_recv().is_ok() {}
+
+        let new = match load_config(&args) {
+            Ok(new) => new,
+            Err(e) => {
+                emitter.error(config::MODULE, e);
+                continue;
+            }
+        };
+
+        let changed = config.changed_modules(&new);
 
-        if let Err(e) = listener
-            .listen_for_changes(|e, info| {
-                let event = SpotifyEvent {
-                    kind: SpotifyEventKind::try_from(e).unwrap_or(SpotifyEventKind::Stopped),
-                    track_info: info,
-                };
-
-                let payload = Payload::new(event);
-
-                println!(
-                    "{}",
-                    serde_json::to_string(&payload).expect("Failed to serialize Spotify event")
-                );
-            })
-            .await
-        {
-            eprintln!("Error in Spotify listener: {}", e);
+        if changed.is_empty() {
+            continue;
         }
-    });
 
-    tokio::select! {
-        _ = sysinfo_handle => {},
-        _ = hyprland_handle => {},
-        _ = spotify_handle => {},
-        _ = monitor_volume_changes() => {},
+        sources.replace(enabled_sources(&new, &args), &changed);
+        config = new;
+    }
+}
+
+// The file with CLI flags on top, validated as a whole
+pub fn load_config(args: &Args) -> Result<Config, BackendError> {
+    let mut config = Config::load(args.config.as_deref())?;
+
+    if let Some(interval) = args.sysinfo_poll_interval {
+        config.sysinfo.poll_interval = interval;
+    }
+
+    if let Some(mount) = &args.mount {
+        config.sysinfo.mount = mount.clone();
+    }
+
+    if let Some(player) = &args.player {
+        config.spotify.player = player.clone();
     }
 
-    Ok(())
+    if let Some(sink) = &args.sink {
+        config.volume.sink = sink.clone();
+    }
+
+    config.validate()?;
+
+    Ok(config)
+}
+
+fn enabled_sources(config: &Config, args: &Args) -> Vec<Source> {
+    if args.simulate {
+        return simulate::sources(config, &args.edge_cases);
+    }
+
+    REGISTRY
+        .iter()
+        .flat_map(|register| register(config))
+        .collect()
 }
 
-// fn handle_hyprland_event(event_line: &str) {
-//     // Parse the event line format: "EVENT>>DATA"
-//     if let Some((event_type, event_data)) = event_line.split_once(">>") {
-//         match event_type {
-//             "workspace" => {
-//                 println!("🖥️  Workspace changed: {}", event_data);
-//             }
-//             "focusedmon" => {
-//                 println!("🖱️  Monitor focus changed: {}", event_data);
-//             }
-//             "activewindow" => {
-//                 println!("🪟  Active window changed: {}", event_data);
-//             }
-//             "activewindowv2" => {
-//                 println!("🪟  Active window changed (v2): {}", event_data);
-//             }
-//             "fullscreen" => {
-//                 println!("⛶  Fullscreen toggled: {}", event_data);
-//             }
-//             "monitorremoved" => {
-//                 println!("📺  Monitor removed: {}", event_data);
-//             }
-//             "monitoradded" => {
-//                 println!("📺  Monitor added: {}", event_data);
-//             }
-//             "createworkspace" => {
-//                 println!("➕  Workspace created: {}", event_data);
-//             }
-//             "destroyworkspace" => {
-//                 println!("➖  Workspace destroyed: {}", event_data);
-//             }
-//             "moveworkspace" => {
-//                 println!("🔄  Workspace moved: {}", event_data);
-//             }
-//             "renameworkspace" => {
-//                 println!("📝  Workspace renamed: {}", event_data);
-//             }
-//             "activelayout" => {
-//                 println!("📐  Layout changed: {}", event_data);
-//             }
-//             "openwindow" => {
-//                 println!("🆕  Window opened: {}", event_data);
-//             }
-//             "closewindow" => {
-//                 println!("❌  Window closed: {}", event_data);
-//             }
-//             "movewindow" => {
-//                 println!("↔️  Window moved: {}", event_data);
-//             }
-//             "openlayer" => {
-//                 println!("🔗  Layer opened: {}", event_data);
-//             }
-//             "closelayer" => {
-//                 println!("🔗  Layer closed: {}", event_data);
-//             }
-//             "submap" => {
-//                 println!("🗺️  Submap changed: {}", event_data);
-//             }
-//             "changefloatingmode" => {
-//                 println!("🎈  Floating mode changed: {}", event_data);
-//             }
-//             "urgent" => {
-//                 println!("⚠️  Urgent window: {}", event_data);
-//             }
-//             "minimize" => {
-//                 println!("📉  Window minimized: {}", event_data);
-//             }
-//             "screencast" => {
-//                 println!("📹  Screencast event: {}", event_data);
-//             }
-//             "windowtitle" => {
-//                 println!("📄  Window title changed: {}", event_data);
-//             }
-//             _ => {
-//                 println!("❓  Unknown event: {} >> {}", event_type, event_data);
-//             }
-//         }
-//     } else {
-//         println!("⚠️  Malformed event line: {}", event_line);
-//     }
-// }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs};
+
+    fn load(name: &str, contents: &str, flags: &[&str]) -> Result<Config, BackendError> {
+        let path = env::temp_dir().join(format!("skadi-{}-{}.toml", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+
+        let mut argv = vec!["backend", "--config", path.to_str().unwrap()];
+        argv.extend_from_slice(flags);
+        let config = load_config(&Args::parse_from(argv));
+
+        fs::remove_file(&path).unwrap();
+        config
+    }
+
+    #[test]
+    fn cli_flags_override_the_file() {
+        let contents =
+            "[sysinfo]\npoll_interval = 10\nmount = \"/home\"\n\n[volume]\nsink = \"hdmi\"";
+
+        let config = load("override", contents, &["--sysinfo-poll-interval", "2"]).unwrap();
+
+        assert_eq!(config.sysinfo.poll_interval, 2);
+        assert_eq!(config.sysinfo.mount, PathBuf::from("/home"));
+        assert_eq!(config.volume.sink, "hdmi");
+    }
+
+    #[test]
+    fn validates_the_overridden_values() {
+        let error = load("invalid", "", &["--mount", "relative"]).unwrap_err();
+
+        assert!(
+            matches!(&error, BackendError::InvalidConfig(field, _) if field == "sysinfo.mount"),
+            "{:?}",
+            error
+        );
+    }
+}
@@ -1,27 +1,79 @@
-use serde::Serialize;
+// Tripped by the impl ts-rs generates for `OpCode`'s `repr(enum)`
+#![allow(clippy::from_str_radix_10)]
+
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, str::FromStr};
+use ts_rs::TS;
 
 use crate::err::BackendError;
 
+// Bumped whenever a change to the stream would break an existing consumer
+pub const PROTOCOL_VERSION: u16 = 1;
+
 pub trait PayloadData: Serialize {
     fn op(&self) -> OpCode;
+
+    fn retain(&self) -> Retain {
+        Retain::Latest
+    }
+}
+
+// What the hub remembers of a payload, to replay it to clients connecting later
+pub enum Retain {
+    Latest,
+    Entry(String),
+    Remove(String),
+    Nothing,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, TS, JsonSchema)]
+pub struct Meta {
+    pub version: u16,
+    // Counted per opcode, so a gap in what a client subscribed to means something was dropped
+    #[ts(type = "number")]
+    pub seq: u64,
+    // Milliseconds since the Unix epoch
+    #[ts(type = "number")]
+    pub time: u64,
+    // Milliseconds since the hub started, unaffected by changes to the wall clock
+    #[ts(type = "number")]
+    pub mono: u64,
 }
 
-#[derive(Serialize)]
-pub struct Payload<T: PayloadData> {
+#[derive(Debug, Serialize, Deserialize, TS, JsonSchema)]
+pub struct Payload<T> {
+    #[ts(as = "OpCode")]
+    #[schemars(with = "OpCode")]
     pub op: u16,
+    #[serde(flatten)]
+    pub meta: Meta,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[ts(as = "Option<bool>", optional)]
+    pub snapshot: bool,
     pub data: T,
 }
 
-impl<T: PayloadData> Payload<T> {
-    pub fn new(data: T) -> Self {
-        Self {
-            op: data.op().into(),
-            data,
-        }
+// First payload on every connection, before any snapshot
+#[derive(Debug, Serialize, Deserialize, TS, JsonSchema)]
+pub struct Hello {
+    pub version: u16,
+    pub sources: Vec<String>,
+}
+
+impl PayloadData for Hello {
+    fn op(&self) -> OpCode {
+        OpCode::Hello
+    }
+
+    fn retain(&self) -> Retain {
+        Retain::Nothing
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, TS)]
+#[serde(into = "u16", try_from = "u16")]
+#[ts(repr(enum))]
 #[repr(u16)]
 pub enum OpCode {
     Error = 0,
@@ -30,6 +82,62 @@ pub enum OpCode {
     Workspace = 3,
     SpotifyEvent = 4,
     VolumeEvent = 5,
+    Reply = 6,
+    Health = 7,
+    Hello = 8,
+    Custom = 9,
+    WorkspaceState = 10,
+    Hyprland = 11,
+}
+
+impl OpCode {
+    pub const ALL: [OpCode; 12] = [
+        OpCode::Error,
+        OpCode::Sysinfo,
+        OpCode::WindowChanged,
+        OpCode::Workspace,
+        OpCode::SpotifyEvent,
+        OpCode::VolumeEvent,
+        OpCode::Reply,
+        OpCode::Health,
+        OpCode::Hello,
+        OpCode::Custom,
+        OpCode::WorkspaceState,
+        OpCode::Hyprland,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            OpCode::Error => "error",
+            OpCode::Sysinfo => "sysinfo",
+            OpCode::WindowChanged => "window",
+            OpCode::Workspace => "workspace",
+            OpCode::SpotifyEvent => "spotify",
+            OpCode::VolumeEvent => "volume",
+            OpCode::Reply => "reply",
+            OpCode::Health => "health",
+            OpCode::Hello => "hello",
+            OpCode::Custom => "custom",
+            OpCode::WorkspaceState => "workspaceState",
+            OpCode::Hyprland => "hyprland",
+        }
+    }
+}
+
+// Serialized through `u16`, but only the assigned values are valid
+impl JsonSchema for OpCode {
+    fn schema_name() -> Cow<'static, str> {
+        "OpCode".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        let values: Vec<u16> = OpCode::ALL.iter().map(|op| u16::from(*op)).collect();
+
+        json_schema!({
+            "type": "integer",
+            "enum": values,
+        })
+    }
 }
 
 impl From<OpCode> for u16 {
@@ -45,7 +153,28 @@ impl TryFrom<u16> for OpCode {
         match value {
             0 => Ok(OpCode::Error),
             1 => Ok(OpCode::Sysinfo),
+            2 => Ok(OpCode::WindowChanged),
+            3 => Ok(OpCode::Workspace),
+            4 => Ok(OpCode::SpotifyEvent),
+            5 => Ok(OpCode::VolumeEvent),
+            6 => Ok(OpCode::Reply),
+            7 => Ok(OpCode::Health),
+            8 => Ok(OpCode::Hello),
+            9 => Ok(OpCode::Custom),
+            10 => Ok(OpCode::WorkspaceState),
+            11 => Ok(OpCode::Hyprland),
             _ => Err(BackendError::InvalidOpCode(value)),
         }
     }
 }
+
+impl FromStr for OpCode {
+    type Err = BackendError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        OpCode::ALL
+            .into_iter()
+            .find(|op| op.name() == s)
+            .ok_or_else(|| BackendError::InvalidOpCodeName(s.to_string()))
+    }
+}
@@ -0,0 +1,217 @@
+use backend::{
+    err::BackendError,
+    event::Event,
+    payload::{OpCode, Payload},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use std::{
+    collections::HashMap,
+    path::Path,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    fs::{self, File},
+    io::AsyncWriteExt,
+    time::Instant,
+};
+
+use crate::{Args, client};
+
+// One line of a recording, with the payload exactly as the hub sent it
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    // Milliseconds since the recording started
+    at: u64,
+    payload: Box<RawValue>,
+}
+
+// Playback rate, written like `2x` or `0.5x`
+#[derive(Debug, Clone, Copy)]
+pub struct Speed(f64);
+
+impl FromStr for Speed {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let factor: f64 = s
+            .strip_suffix('x')
+            .unwrap_or(s)
+            .parse()
+            .map_err(|_| format!("Invalid speed {}, expected e.g. 2x", s))?;
+
+        if !factor.is_finite() || factor <= 0.0 {
+            return Err(format!("Invalid speed {}, must be above 0", s));
+        }
+
+        Ok(Speed(factor))
+    }
+}
+
+// Writes every payload with its arrival time until the hub goes away or the process is stopped
+pub async fn record(args: &Args, path: &Path) -> Result<(), BackendError> {
+    // The write half stays open, the hub takes a closed one as a disconnect
+    let (mut events, _write) = client::subscribe(args).await?;
+    let mut file = File::create(path).await?;
+    let started = Instant::now();
+
+    while let Some(payload) = events.next().await? {
+        let payload = serde_json::to_string(&payload).expect("Failed to serialize payload");
+        let entry = Entry {
+            at: started.elapsed().as_millis() as u64,
+            payload: RawValue::from_string(payload).expect("Serialized payload is valid JSON"),
+        };
+
+        // Flushed line by line, so stopping the recording loses nothing
+        let mut line = serde_json::to_string(&entry).expect("Failed to serialize recording");
+        line.push('\n');
+        file.write_all(line.as_bytes()).await?;
+        file.flush().await?;
+    }
+
+    Ok(())
+}
+
+// Prints the recorded payloads with their original spacing. `time` is stamped when printed, while `mono`
+// keeps the recorded spacing at any speed so rates come out the same. Every pass after the first carries
+// on where the previous one ended, so sequence numbers and `mono` keep growing like a live hub's
+pub async fn replay(path: &Path, speed: Speed, looped: bool) -> Result<(), BackendError> {
+    let entries = read(path).await?;
+
+    if entries.is_empty() {
+        return Err(BackendError::InvalidRecording(
+            path.display().to_string(),
+            "it is empty".to_string(),
+        ));
+    }
+
+    let (gap, span) = pass_timing(&entries);
+
+    let mut max_seqs: HashMap<u16, u64> = HashMap::new();
+
+    for (_, payload) in &entries {
+        let seq = max_seqs.entry(payload.op).or_default();
+        *seq = (*seq).max(payload.meta.seq);
+    }
+
+    let mut stdout = tokio::io::stdout();
+    let mut pass = 0;
+
+    loop {
+        let started = Instant::now();
+
+        for (at, payload) in &entries {
+            // A hub greets a connection once
+            if pass > 0 && payload.op == u16::from(OpCode::Hello) {
+                continue;
+            }
+
+            let delay = Duration::from_secs_f64(*at as f64 / 1000.0 / speed.0);
+            tokio::time::sleep_until(started + delay).await;
+
+            let mut meta = payload.meta;
+            meta.time = now_millis();
+            meta.mono += pass * span;
+            meta.seq += pass * max_seqs[&payload.op];
+
+            let payload = Payload {
+                op: payload.op,
+                meta,
+                snapshot: payload.snapshot,
+                data: &payload.data,
+            };
+
+            let mut line = serde_json::to_string(&payload).expect("Failed to serialize payload");
+            line.push('\n');
+            stdout.write_all(line.as_bytes()).await?;
+            stdout.flush().await?;
+        }
+
+        if !looped {
+            return Ok(());
+        }
+
+        tokio::time::sleep(Duration::from_secs_f64(gap as f64 / 1000.0 / speed.0)).await;
+        pass += 1;
+    }
+}
+
+// A pass takes as long as the recording did, then waits the average spacing of its payloads before the
+// next one starts. Returns that gap and how far `mono` moves on each pass, both in milliseconds
+fn pass_timing(entries: &[(u64, Payload<Event>)]) -> (u64, u64) {
+    let monos = || entries.iter().map(|(_, payload)| payload.meta.mono);
+    let first = monos().min().unwrap_or(0);
+    let last = monos().max().unwrap_or(0);
+
+    let gap = ((last - first) / (entries.len() as u64).saturating_sub(1).max(1)).max(1);
+
+    (gap, last - first + gap)
+}
+
+async fn read(path: &Path) -> Result<Vec<(u64, Payload<Event>)>, BackendError> {
+    let contents = fs::read_to_string(path).await?;
+
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let entry: Entry =
+                serde_json::from_str(line).map_err(|e| invalid(path, i + 1, &e.to_string()))?;
+            let payload = Payload::parse(entry.payload.get())
+                .map_err(|e| invalid(path, i + 1, &e.to_string()))?;
+
+            Ok((entry.at, payload))
+        })
+        .collect()
+}
+
+fn invalid(path: &Path, line: usize, reason: &str) -> BackendError {
+    BackendError::InvalidRecording(format!("{}:{}", path.display(), line), reason.to_string())
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entries(monos: &[u64]) -> Vec<(u64, Payload<Event>)> {
+        monos
+            .iter()
+            .enumerate()
+            .map(|(i, &mono)| {
+                let line = json!({
+                    "op": u16::from(OpCode::VolumeEvent),
+                    "version": 1,
+                    "seq": i,
+                    "time": 0,
+                    "mono": mono,
+                    "data": { "volume": 50 },
+                });
+
+                (mono - monos[0], Payload::parse(&line.to_string()).unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn loops_on_where_the_recording_ended() {
+        let (gap, span) = pass_timing(&entries(&[5000, 5100, 5400, 5600]));
+
+        assert_eq!(gap, 200);
+        // The second pass starts one gap after the last payload of the first
+        assert_eq!(5000 + span, 5600 + gap);
+    }
+
+    #[test]
+    fn loops_a_single_payload_a_millisecond_apart() {
+        assert_eq!(pass_timing(&entries(&[5000])), (1, 1));
+    }
+}
@@ -0,0 +1,73 @@
+use schemars::{JsonSchema, Schema, generate::SchemaSettings, schema_for};
+use serde_json::{Map, Value, json};
+use std::{fs, path::Path};
+
+use crate::{
+    command::{CommandReply, CommandRequest},
+    custom::CustomEvent,
+    err::{BackendError, ErrorPayload},
+    hub::ClientMessage,
+    hyprland::{WindowChanged, Workspace, WorkspaceState},
+    hyprland_event::HyprlandEvent,
+    payload::{Hello, OpCode, PROTOCOL_VERSION, Payload},
+    sources::SourceHealth,
+    spotify::SpotifyEvent,
+    sysinfo::SysinfoPayload,
+    volume::VolumeEvent,
+};
+
+// Describes what the hub writes, where fields it skips are optional
+fn output_schema<T: JsonSchema>() -> Schema {
+    SchemaSettings::default()
+        .for_serialize()
+        .into_generator()
+        .into_root_schema_for::<T>()
+}
+
+// Exhaustive, so a new opcode can't ship without a schema for its data
+fn payload_schema(op: OpCode) -> Schema {
+    match op {
+        OpCode::Error => output_schema::<ErrorPayload>(),
+        OpCode::Sysinfo => output_schema::<SysinfoPayload>(),
+        OpCode::WindowChanged => output_schema::<WindowChanged>(),
+        OpCode::Workspace => output_schema::<Workspace>(),
+        OpCode::SpotifyEvent => output_schema::<SpotifyEvent>(),
+        OpCode::VolumeEvent => output_schema::<VolumeEvent>(),
+        OpCode::Reply => output_schema::<CommandReply>(),
+        OpCode::Health => output_schema::<SourceHealth>(),
+        OpCode::Hello => output_schema::<Hello>(),
+        OpCode::Custom => output_schema::<CustomEvent>(),
+        OpCode::WorkspaceState => output_schema::<WorkspaceState>(),
+        OpCode::Hyprland => output_schema::<HyprlandEvent>(),
+    }
+}
+
+// The envelope leaves `data` open, the schema for it is found under its opcode in `payloads`
+pub fn document() -> Value {
+    let payloads: Map<String, Value> = OpCode::ALL
+        .iter()
+        .map(|op| (u16::from(*op).to_string(), payload_schema(*op).into()))
+        .collect();
+
+    json!({
+        "version": PROTOCOL_VERSION,
+        "envelope": output_schema::<Payload<Value>>(),
+        "payloads": payloads,
+        "commands": schema_for!(CommandRequest),
+        "clientMessage": schema_for!(ClientMessage),
+    })
+}
+
+pub fn write(out: Option<&Path>) -> Result<(), BackendError> {
+    // Only built from in-memory values, so this can't fail
+    let mut document =
+        serde_json::to_string_pretty(&document()).expect("Failed to serialize schema");
+    document.push('\n');
+
+    match out {
+        Some(path) => fs::write(path, document)?,
+        None => print!("{}", document),
+    }
+
+    Ok(())
+}
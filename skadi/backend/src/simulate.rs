@@ -0,0 +1,962 @@
+use clap::ValueEnum;
+use std::{
+    collections::BTreeMap,
+    f64::consts::TAU,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{sync::Notify, time::Instant};
+
+use crate::{
+    command::Command,
+    config::Config,
+    err::BackendError,
+    hub::Emitter,
+    hyprland::{
+        self, MonitorInfo, WindowChanged, WindowInfo, Workspace, WorkspaceEventKind, WorkspaceInfo,
+        WorkspaceState,
+    },
+    hyprland_event::HyprlandEvent,
+    payload::OpCode,
+    sources::{EventSource, Source, SourceFuture},
+    spotify::{self, MediaAction, SpotifyEvent, SpotifyEventKind, TrackInfo},
+    sysinfo::{self, SysinfoPayload},
+    volume::{self, VolumeEvent},
+};
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EdgeCase {
+    /// Track and window titles far wider than the bar
+    LongTitles,
+    /// Tracks without an artist or album
+    EmptyArtist,
+    /// Workspaces up to 10, the first id with two digits
+    #[value(name = "workspace-10")]
+    Workspace10,
+    /// Volume going up to 150%
+    #[value(name = "volume-150")]
+    Volume150,
+    /// A special workspace shown and hidden now and then
+    Scratchpad,
+}
+
+// Stand-ins for the enabled built-in modules, generating plausible data instead of reading the system.
+// They take the same commands, so the widgets can be clicked through
+pub fn sources(config: &Config, edge_cases: &[EdgeCase]) -> Vec<Source> {
+    let mut sources: Vec<Source> = Vec::new();
+
+    if config.sysinfo.enabled {
+        sources.push(Arc::new(SimulatedSysinfo {
+            interval: config.sysinfo.interval(),
+        }));
+    }
+
+    if config.hyprland.enabled {
+        sources.push(Arc::new(SimulatedHyprland::new(edge_cases)));
+    }
+
+    if config.spotify.enabled {
+        sources.push(Arc::new(SimulatedSpotify::new(edge_cases)));
+    }
+
+    if config.volume.enabled {
+        sources.push(Arc::new(SimulatedVolume::new(edge_cases)));
+    }
+
+    sources
+}
+
+// Xorshift, plenty for fake data and saves a dependency
+struct Rng(u64);
+
+impl Rng {
+    fn new() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+
+        Self(seed | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    // In `[0, 1)`
+    fn float(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // In `low..=high`
+    fn between(&mut self, low: u64, high: u64) -> u64 {
+        low + self.next() % (high - low + 1)
+    }
+
+    fn chance(&mut self, probability: f64) -> bool {
+        self.float() < probability
+    }
+
+    fn pick<T: Copy>(&mut self, items: &[T]) -> Option<T> {
+        (!items.is_empty()).then(|| items[self.next() as usize % items.len()])
+    }
+
+    fn delay(&mut self, low_ms: u64, high_ms: u64) -> Duration {
+        Duration::from_millis(self.between(low_ms, high_ms))
+    }
+}
+
+// Events caused by commands, emitted by the source's running task
+struct Pending<T> {
+    queue: Mutex<Vec<T>>,
+    notify: Notify,
+}
+
+impl<T> Pending<T> {
+    fn new() -> Self {
+        Self {
+            queue: Mutex::new(Vec::new()),
+            notify: Notify::new(),
+        }
+    }
+
+    fn push(&self, events: impl IntoIterator<Item = T>) {
+        self.queue.lock().unwrap().extend(events);
+        self.notify.notify_one();
+    }
+
+    async fn wait(&self) -> Vec<T> {
+        self.notify.notified().await;
+        std::mem::take(&mut *self.queue.lock().unwrap())
+    }
+}
+
+fn long_title(title: &str) -> String {
+    format!(
+        "{} (Extended Deluxe Anniversary Remaster) [Live at the Royal Albert Hall] - {}",
+        title,
+        "a title that keeps on going ".repeat(8).trim_end()
+    )
+}
+
+struct SimulatedSysinfo {
+    interval: Duration,
+}
+
+impl EventSource for SimulatedSysinfo {
+    fn name(&self) -> &str {
+        sysinfo::MODULE
+    }
+
+    fn ops(&self) -> &'static [OpCode] {
+        &[OpCode::Sysinfo]
+    }
+
+    fn start(&self, emitter: Emitter) -> SourceFuture<'_, Result<(), BackendError>> {
+        Box::pin(async move {
+            let mut rng = Rng::new();
+            let mut ticker = tokio::time::interval(self.interval);
+            let started = Instant::now();
+            let seconds = self.interval.as_secs_f64();
+
+            let mut memory_used = 6 * GIB;
+            let mut disk_usage = 320 * GIB;
+
+            loop {
+                ticker.tick().await;
+
+                let t = started.elapsed().as_secs_f64();
+
+                // A slow wave with noise on top and the odd spike
+                let spike = if rng.chance(0.1) { 40.0 } else { 0.0 };
+                let cpu_usage = 35.0 + 25.0 * (t * TAU / 60.0).sin() + rng.float() * 10.0 + spike;
+
+                memory_used = (memory_used + rng.between(0, GIB / 2))
+                    .saturating_sub(GIB / 4)
+                    .clamp(2 * GIB, 14 * GIB);
+
+                // Mostly a trickle, now and then a download. Counters are per interval, like the real ones
+                let rx_rate = if rng.chance(0.2) {
+                    rng.between(2_000_000, 40_000_000)
+                } else {
+                    rng.between(1_000, 200_000)
+                };
+                let tx_rate = rng.between(1_000, 100_000);
+                let disk_write = (rng.between(0, 5_000_000) as f64 * seconds) as u64;
+
+                // Fills up after days of running, then stays full
+                disk_usage = (disk_usage + disk_write / 4).min(512 * GIB);
+
+                emitter.emit(SysinfoPayload {
+                    cpu_usage: cpu_usage.clamp(0.0, 100.0) as f32,
+                    memory_used,
+                    memory_total: 16 * GIB,
+                    memory_free: 16 * GIB - memory_used,
+                    swap_used: GIB / 2,
+                    swap_total: 8 * GIB,
+                    network_rx: (rx_rate as f64 * seconds) as u64,
+                    network_tx: (tx_rate as f64 * seconds) as u64,
+                    disk_read: (rng.between(0, 20_000_000) as f64 * seconds) as u64,
+                    disk_write,
+                    disk_usage,
+                    disk_total: 512 * GIB,
+                    disk_free: 512 * GIB - disk_usage,
+                });
+            }
+        })
+    }
+}
+
+struct Desktop {
+    // The monitor of every workspace, as an index into `MONITORS`
+    workspaces: BTreeMap<u16, usize>,
+    // Active workspace of each monitor
+    active: [u16; 2],
+    focused: usize,
+    title: String,
+    // The monitor the scratchpad was last shown on and whether it still is, `None` without one
+    scratchpad: Option<(usize, bool)>,
+}
+
+impl Desktop {
+    // Named like the bars in the frontend config, so each one shows its own workspaces
+    const MONITORS: [&str; 2] = ["DP-1", "eDP-1"];
+    const SCRATCHPAD_ID: i32 = -98;
+    const SCRATCHPAD: &str = "special:magic";
+
+    // A window on every workspace, the focused one on the active workspace of the focused monitor
+    fn state(&self) -> WorkspaceState {
+        let scratchpad = self.scratchpad.map(|(monitor, _)| WorkspaceInfo {
+            id: Self::SCRATCHPAD_ID,
+            name: Self::SCRATCHPAD.to_string(),
+            monitor: Self::MONITORS[monitor].to_string(),
+            windows: 1,
+        });
+
+        WorkspaceState {
+            workspaces: scratchpad
+                .into_iter()
+                .chain(self.workspaces.iter().map(|(id, monitor)| WorkspaceInfo {
+                    id: i32::from(*id),
+                    name: id.to_string(),
+                    monitor: Self::MONITORS[*monitor].to_string(),
+                    windows: 1,
+                }))
+                .collect(),
+            monitors: Self::MONITORS
+                .iter()
+                .enumerate()
+                .map(|(monitor, name)| MonitorInfo {
+                    name: name.to_string(),
+                    active_workspace: i32::from(self.active[monitor]),
+                    special_workspace: (self.scratchpad == Some((monitor, true)))
+                        .then_some(Self::SCRATCHPAD_ID),
+                    focused: monitor == self.focused,
+                })
+                .collect(),
+            focused_window: Some(WindowInfo {
+                address: format!("0x{:012x}", self.active[self.focused]),
+                class: "simulated".to_string(),
+                title: self.title.clone(),
+                workspace: i32::from(self.active[self.focused]),
+            }),
+        }
+    }
+
+    fn change(&self, kind: WorkspaceEventKind, id: u16) -> Workspace {
+        Workspace {
+            kind,
+            id: i32::from(id),
+            name: id.to_string(),
+            monitor: Self::MONITORS[self.workspaces[&id]].to_string(),
+        }
+    }
+
+    fn scratchpad_change(kind: WorkspaceEventKind, monitor: usize) -> Workspace {
+        Workspace {
+            kind,
+            id: Self::SCRATCHPAD_ID,
+            name: Self::SCRATCHPAD.to_string(),
+            monitor: Self::MONITORS[monitor].to_string(),
+        }
+    }
+
+    // Shown on the focused monitor, hidden from whichever it is on
+    fn toggle_scratchpad(&mut self) -> Vec<Workspace> {
+        let Some((monitor, shown)) = &mut self.scratchpad else {
+            return Vec::new();
+        };
+
+        let kind = if *shown {
+            WorkspaceEventKind::SpecialHidden
+        } else {
+            *monitor = self.focused;
+            WorkspaceEventKind::SpecialShown
+        };
+
+        *shown = !*shown;
+
+        vec![Desktop::scratchpad_change(kind, *monitor)]
+    }
+
+    // A new workspace opens on the focused monitor, one on the other monitor moves focus there
+    fn focus(&mut self, id: u16) -> Vec<Workspace> {
+        let mut events = Vec::new();
+
+        if !self.workspaces.contains_key(&id) {
+            self.workspaces.insert(id, self.focused);
+            events.push(self.change(WorkspaceEventKind::Created, id));
+        }
+
+        let monitor = self.workspaces[&id];
+        self.focused = monitor;
+        self.active[monitor] = id;
+        events.push(self.change(WorkspaceEventKind::Moved, id));
+
+        events
+    }
+
+    // Creates, destroys, switches to or moves a workspace to the other monitor, or focuses that monitor.
+    // Falls back to switching when the others can't happen
+    fn random_change(&mut self, rng: &mut Rng, max_id: u16) -> Vec<Workspace> {
+        if self.scratchpad.is_some() && rng.chance(0.2) {
+            return self.toggle_scratchpad();
+        }
+
+        // Those shown on neither monitor
+        let hidden: Vec<u16> = self
+            .workspaces
+            .keys()
+            .copied()
+            .filter(|id| !self.active.contains(id))
+            .collect();
+
+        match rng.next() % 5 {
+            0 => {
+                let free: Vec<u16> = (1..=max_id)
+                    .filter(|id| !self.workspaces.contains_key(id))
+                    .collect();
+
+                if let Some(id) = rng.pick(&free) {
+                    self.workspaces.insert(id, self.focused);
+                    return vec![self.change(WorkspaceEventKind::Created, id)];
+                }
+            }
+            1 => {
+                if let Some(id) = rng.pick(&hidden) {
+                    let change = self.change(WorkspaceEventKind::Destroyed, id);
+                    self.workspaces.remove(&id);
+                    return vec![change];
+                }
+            }
+            2 => {
+                if let Some(id) = rng.pick(&hidden) {
+                    let monitor = self.workspaces[&id];
+                    self.workspaces
+                        .insert(id, (monitor + 1) % Self::MONITORS.len());
+                    return vec![self.change(WorkspaceEventKind::Reassigned, id)];
+                }
+            }
+            3 => {
+                self.focused = (self.focused + 1) % Self::MONITORS.len();
+                return vec![self.change(WorkspaceEventKind::Focused, self.active[self.focused])];
+            }
+            _ => {}
+        }
+
+        match rng.pick(&hidden) {
+            Some(id) => self.focus(id),
+            None => Vec::new(),
+        }
+    }
+}
+
+// Sends a change along with the events Hyprland itself has for it
+fn emit_change(emitter: &Emitter, change: Workspace) {
+    let (id, name, monitor) = (change.id, change.name.clone(), change.monitor.clone());
+
+    let events = match change.kind {
+        WorkspaceEventKind::Moved => vec![
+            HyprlandEvent::Workspace { name: name.clone() },
+            HyprlandEvent::WorkspaceV2 { id, name },
+        ],
+        WorkspaceEventKind::Created => vec![
+            HyprlandEvent::CreateWorkspace { name: name.clone() },
+            HyprlandEvent::CreateWorkspaceV2 { id, name },
+        ],
+        WorkspaceEventKind::Destroyed => vec![
+            HyprlandEvent::DestroyWorkspace { name: name.clone() },
+            HyprlandEvent::DestroyWorkspaceV2 { id, name },
+        ],
+        WorkspaceEventKind::Reassigned => vec![
+            HyprlandEvent::MoveWorkspace {
+                name: name.clone(),
+                monitor: monitor.clone(),
+            },
+            HyprlandEvent::MoveWorkspaceV2 { id, name, monitor },
+        ],
+        WorkspaceEventKind::Focused => vec![
+            HyprlandEvent::FocusedMon {
+                monitor: monitor.clone(),
+                workspace: name,
+            },
+            HyprlandEvent::FocusedMonV2 {
+                monitor,
+                workspace_id: id,
+            },
+        ],
+        WorkspaceEventKind::MonitorRemoved => vec![HyprlandEvent::MonitorRemoved { name: monitor }],
+        WorkspaceEventKind::Renamed => vec![HyprlandEvent::RenameWorkspace { id, name }],
+        WorkspaceEventKind::SpecialShown => vec![
+            HyprlandEvent::ActiveSpecial {
+                name: Some(name.clone()),
+                monitor: monitor.clone(),
+            },
+            HyprlandEvent::ActiveSpecialV2 {
+                id: Some(id),
+                name: Some(name),
+                monitor,
+            },
+        ],
+        WorkspaceEventKind::SpecialHidden => vec![
+            HyprlandEvent::ActiveSpecial {
+                name: None,
+                monitor: monitor.clone(),
+            },
+            HyprlandEvent::ActiveSpecialV2 {
+                id: None,
+                name: None,
+                monitor,
+            },
+        ],
+    };
+
+    for event in events {
+        emitter.emit(event);
+    }
+
+    emitter.emit(change);
+}
+
+struct SimulatedHyprland {
+    max_id: u16,
+    long_titles: bool,
+    desktop: Mutex<Desktop>,
+    pending: Pending<Workspace>,
+}
+
+impl SimulatedHyprland {
+    const TITLES: [&str; 5] = [
+        "foot",
+        "nvim src/main.rs",
+        "Rust Documentation - Firefox",
+        "Discord",
+        "btop",
+    ];
+
+    // Three workspaces on the external monitor and one on the laptop's
+    fn new(edge_cases: &[EdgeCase]) -> Self {
+        let mut workspaces = BTreeMap::from([(1, 0), (2, 0), (3, 0), (4, 1)]);
+
+        if edge_cases.contains(&EdgeCase::Workspace10) {
+            workspaces.insert(10, 1);
+        }
+
+        Self {
+            max_id: if edge_cases.contains(&EdgeCase::Workspace10) {
+                10
+            } else {
+                5
+            },
+            long_titles: edge_cases.contains(&EdgeCase::LongTitles),
+            desktop: Mutex::new(Desktop {
+                workspaces,
+                active: [1, 4],
+                focused: 0,
+                title: Self::TITLES[0].to_string(),
+                scratchpad: edge_cases
+                    .contains(&EdgeCase::Scratchpad)
+                    .then_some((0, false)),
+            }),
+            pending: Pending::new(),
+        }
+    }
+
+    fn window_title(&self, rng: &mut Rng) -> String {
+        let title = rng.pick(&Self::TITLES).unwrap_or_default();
+
+        if self.long_titles && rng.chance(0.5) {
+            long_title(title)
+        } else {
+            title.to_string()
+        }
+    }
+}
+
+impl EventSource for SimulatedHyprland {
+    fn name(&self) -> &str {
+        hyprland::MODULE
+    }
+
+    fn ops(&self) -> &'static [OpCode] {
+        &[
+            OpCode::Workspace,
+            OpCode::WindowChanged,
+            OpCode::WorkspaceState,
+            OpCode::Hyprland,
+        ]
+    }
+
+    fn start(&self, emitter: Emitter) -> SourceFuture<'_, Result<(), BackendError>> {
+        Box::pin(async move {
+            let mut rng = Rng::new();
+
+            loop {
+                let delay = rng.delay(1500, 4000);
+
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {
+                        let events = self.desktop.lock().unwrap().random_change(&mut rng, self.max_id);
+
+                        for event in events {
+                            emit_change(&emitter, event);
+                        }
+
+                        if rng.chance(0.5) {
+                            let title = self.window_title(&mut rng);
+                            self.desktop.lock().unwrap().title = title.clone();
+                            emitter.emit(HyprlandEvent::ActiveWindow {
+                                class: "simulated".to_string(),
+                                title: title.clone(),
+                            });
+                            emitter.emit(WindowChanged { title });
+                        }
+
+                        emitter.emit(self.desktop.lock().unwrap().state());
+                    }
+
+                    events = self.pending.wait() => {
+                        for event in events {
+                            emit_change(&emitter, event);
+                        }
+
+                        emitter.emit(self.desktop.lock().unwrap().state());
+                    }
+                }
+            }
+        })
+    }
+
+    fn snapshot(&self, emitter: &Emitter) -> SourceFuture<'_, Result<(), BackendError>> {
+        let desktop = self.desktop.lock().unwrap();
+
+        for id in desktop.workspaces.keys() {
+            emitter.emit(desktop.change(WorkspaceEventKind::Created, *id));
+        }
+
+        if let Some((monitor, shown)) = desktop.scratchpad {
+            emitter.emit(Desktop::scratchpad_change(
+                WorkspaceEventKind::Created,
+                monitor,
+            ));
+
+            if shown {
+                emitter.emit(Desktop::scratchpad_change(
+                    WorkspaceEventKind::SpecialShown,
+                    monitor,
+                ));
+            }
+        }
+
+        // The focused monitor last, as if it was switched to most recently
+        let others = (0..Desktop::MONITORS.len()).filter(|monitor| *monitor != desktop.focused);
+
+        for monitor in others.chain([desktop.focused]) {
+            emitter.emit(desktop.change(WorkspaceEventKind::Moved, desktop.active[monitor]));
+        }
+
+        emitter.emit(desktop.change(WorkspaceEventKind::Focused, desktop.active[desktop.focused]));
+
+        emitter.emit(desktop.state());
+
+        Box::pin(async { Ok(()) })
+    }
+
+    fn command(&self, command: Command) -> SourceFuture<'_, Result<(), BackendError>> {
+        Box::pin(async move {
+            let Command::Workspace { id } = command else {
+                return Err(BackendError::UnsupportedCommand(
+                    hyprland::MODULE.to_string(),
+                ));
+            };
+
+            let mut desktop = self.desktop.lock().unwrap();
+
+            let events = if id == Desktop::SCRATCHPAD_ID && desktop.scratchpad.is_some() {
+                desktop.toggle_scratchpad()
+            } else {
+                let id = u16::try_from(id)
+                    .ok()
+                    .filter(|id| *id > 0)
+                    .ok_or_else(|| BackendError::InvalidMessage(format!("No workspace {}", id)))?;
+
+                desktop.focus(id)
+            };
+
+            self.pending.push(events);
+
+            Ok(())
+        })
+    }
+}
+
+#[derive(Clone)]
+struct Track {
+    title: String,
+    artist: Option<String>,
+    album: Option<String>,
+    duration: Duration,
+}
+
+impl Track {
+    fn new(title: &str, artist: &str, album: &str, seconds: u64) -> Self {
+        Self {
+            title: title.to_string(),
+            artist: Some(artist.to_string()),
+            album: Some(album.to_string()),
+            duration: Duration::from_secs(seconds),
+        }
+    }
+}
+
+struct Player {
+    track: usize,
+    position: Duration,
+    playing: bool,
+}
+
+struct SimulatedSpotify {
+    tracks: Vec<Track>,
+    player: Mutex<Player>,
+    pending: Pending<()>,
+}
+
+impl SimulatedSpotify {
+    fn new(edge_cases: &[EdgeCase]) -> Self {
+        let mut tracks = vec![
+            Track::new("Midnight Drive", "Neon Coast", "Afterglow", 214),
+            Track::new("Paper Planes", "The Lanterns", "Weather Systems", 187),
+            Track::new("Slow Burn", "Marlowe", "Low Tide", 243),
+            Track::new("Static", "Kid Arcade", "Signal", 162),
+        ];
+
+        // Edge cases go first, so they show up without waiting through the playlist
+        if edge_cases.contains(&EdgeCase::EmptyArtist) {
+            tracks.insert(
+                0,
+                Track {
+                    artist: None,
+                    album: None,
+                    ..Track::new("Untitled Field Recording", "", "", 95)
+                },
+            );
+        }
+
+        if edge_cases.contains(&EdgeCase::LongTitles) {
+            let mut track = Track::new("Overture", "The Very Long Band Name Orchestra", "", 301);
+            track.title = long_title(&track.title);
+            track.album = Some(long_title("Collected Works"));
+            tracks.insert(0, track);
+        }
+
+        Self {
+            tracks,
+            player: Mutex::new(Player {
+                track: 0,
+                position: Duration::ZERO,
+                playing: true,
+            }),
+            pending: Pending::new(),
+        }
+    }
+
+    // Like playerctl, positions and lengths are in microseconds
+    fn event(&self, kind: Option<SpotifyEventKind>) -> SpotifyEvent {
+        let player = self.player.lock().unwrap();
+        let track = &self.tracks[player.track];
+
+        let (status, playing_kind) = if player.playing {
+            ("Playing", SpotifyEventKind::Playing)
+        } else {
+            ("Paused", SpotifyEventKind::Paused)
+        };
+
+        SpotifyEvent {
+            kind: kind.unwrap_or(playing_kind),
+            track_info: TrackInfo {
+                title: Some(track.title.clone()),
+                artist: track.artist.clone(),
+                album: track.album.clone(),
+                status: status.to_string(),
+                position: Some(player.position.as_micros() as u64),
+                duration: Some(track.duration.as_micros() as u64),
+                volume: Some("0.800000".to_string()),
+                artwork_url: None,
+            },
+        }
+    }
+
+    fn skip(&self, player: &mut Player, forward: bool) {
+        let count = self.tracks.len();
+
+        player.track = if forward {
+            (player.track + 1) % count
+        } else {
+            (player.track + count - 1) % count
+        };
+        player.position = Duration::ZERO;
+    }
+}
+
+impl EventSource for SimulatedSpotify {
+    fn name(&self) -> &str {
+        spotify::MODULE
+    }
+
+    fn ops(&self) -> &'static [OpCode] {
+        &[OpCode::SpotifyEvent]
+    }
+
+    // Reports every second while playing, like `playerctl --follow` does with a position in its format
+    fn start(&self, emitter: Emitter) -> SourceFuture<'_, Result<(), BackendError>> {
+        Box::pin(async move {
+            let second = Duration::from_secs(1);
+            let mut ticker = tokio::time::interval_at(Instant::now() + second, second);
+
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {
+                        let mut player = self.player.lock().unwrap();
+
+                        if !player.playing {
+                            continue;
+                        }
+
+                        player.position += second;
+
+                        if player.position >= self.tracks[player.track].duration {
+                            self.skip(&mut player, true);
+                        }
+                    }
+
+                    _ = self.pending.wait() => {}
+                }
+
+                emitter.emit(self.event(None));
+            }
+        })
+    }
+
+    fn snapshot(&self, emitter: &Emitter) -> SourceFuture<'_, Result<(), BackendError>> {
+        emitter.emit(self.event(Some(SpotifyEventKind::Request)));
+        Box::pin(async { Ok(()) })
+    }
+
+    fn command(&self, command: Command) -> SourceFuture<'_, Result<(), BackendError>> {
+        Box::pin(async move {
+            let Command::Media { action } = command else {
+                return Err(BackendError::UnsupportedCommand(
+                    spotify::MODULE.to_string(),
+                ));
+            };
+
+            {
+                let mut player = self.player.lock().unwrap();
+
+                match action {
+                    MediaAction::Play => player.playing = true,
+                    MediaAction::Pause => player.playing = false,
+                    MediaAction::PlayPause => player.playing = !player.playing,
+                    MediaAction::Next => self.skip(&mut player, true),
+                    MediaAction::Previous => self.skip(&mut player, false),
+                }
+            }
+
+            self.pending.push([()]);
+
+            Ok(())
+        })
+    }
+}
+
+struct SimulatedVolume {
+    max: u32,
+    volume: Mutex<u32>,
+    pending: Pending<()>,
+}
+
+impl SimulatedVolume {
+    fn new(edge_cases: &[EdgeCase]) -> Self {
+        let loud = edge_cases.contains(&EdgeCase::Volume150);
+
+        Self {
+            max: if loud { 150 } else { 100 },
+            volume: Mutex::new(if loud { 120 } else { 50 }),
+            pending: Pending::new(),
+        }
+    }
+
+    fn set(&self, volume: i64) {
+        *self.volume.lock().unwrap() = volume.clamp(0, self.max as i64) as u32;
+    }
+
+    fn current(&self) -> VolumeEvent {
+        VolumeEvent {
+            volume: *self.volume.lock().unwrap(),
+        }
+    }
+}
+
+impl EventSource for SimulatedVolume {
+    fn name(&self) -> &str {
+        volume::MODULE
+    }
+
+    fn ops(&self) -> &'static [OpCode] {
+        &[OpCode::VolumeEvent]
+    }
+
+    fn start(&self, emitter: Emitter) -> SourceFuture<'_, Result<(), BackendError>> {
+        Box::pin(async move {
+            let mut rng = Rng::new();
+
+            loop {
+                let delay = rng.delay(700, 2500);
+
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {
+                        let step = rng.between(1, 4) as i64;
+                        let step = if rng.chance(0.5) { step } else { -step };
+                        self.set(self.current().volume as i64 + step);
+                    }
+
+                    _ = self.pending.wait() => {}
+                }
+
+                emitter.emit(self.current());
+            }
+        })
+    }
+
+    fn snapshot(&self, emitter: &Emitter) -> SourceFuture<'_, Result<(), BackendError>> {
+        emitter.emit(self.current());
+        Box::pin(async { Ok(()) })
+    }
+
+    // Muting leaves the volume as is, which is what pactl reports too
+    fn command(&self, command: Command) -> SourceFuture<'_, Result<(), BackendError>> {
+        Box::pin(async move {
+            match command {
+                Command::VolumeSet { volume } => self.set(volume as i64),
+                Command::VolumeStep { delta } => {
+                    self.set(self.current().volume as i64 + delta as i64)
+                }
+                Command::VolumeMute => {}
+                _ => return Err(BackendError::UnsupportedCommand(volume::MODULE.to_string())),
+            }
+
+            self.pending.push([()]);
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace_ids(hyprland: &SimulatedHyprland) -> Vec<i32> {
+        let state = hyprland.desktop.lock().unwrap().state();
+        state
+            .workspaces
+            .iter()
+            .map(|workspace| workspace.id)
+            .collect()
+    }
+
+    #[test]
+    fn mixes_in_long_titles() {
+        let spotify = SimulatedSpotify::new(&[EdgeCase::LongTitles]);
+        let track = spotify.event(None).track_info;
+        assert!(track.title.unwrap().len() > 200);
+        assert!(track.album.unwrap().len() > 200);
+
+        let hyprland = SimulatedHyprland::new(&[EdgeCase::LongTitles]);
+        let mut rng = Rng::new();
+        assert!((0..100).any(|_| hyprland.window_title(&mut rng).len() > 200));
+
+        let hyprland = SimulatedHyprland::new(&[]);
+        assert!((0..100).all(|_| hyprland.window_title(&mut rng).len() < 50));
+    }
+
+    #[test]
+    fn mixes_in_a_track_without_artist() {
+        let track = SimulatedSpotify::new(&[EdgeCase::EmptyArtist])
+            .event(None)
+            .track_info;
+        assert_eq!((track.artist, track.album), (None, None));
+
+        let track = SimulatedSpotify::new(&[]).event(None).track_info;
+        assert!(track.artist.is_some());
+    }
+
+    #[test]
+    fn mixes_in_workspace_10() {
+        let hyprland = SimulatedHyprland::new(&[EdgeCase::Workspace10]);
+        assert!(workspace_ids(&hyprland).contains(&10));
+
+        let mut rng = Rng::new();
+        let mut desktop = hyprland.desktop.lock().unwrap();
+        let changed: Vec<i32> = (0..1000)
+            .flat_map(|_| desktop.random_change(&mut rng, hyprland.max_id))
+            .map(|change| change.id)
+            .collect();
+        assert!(changed.iter().all(|id| (1..=10).contains(id)));
+
+        assert!(!workspace_ids(&SimulatedHyprland::new(&[])).contains(&10));
+    }
+
+    #[test]
+    fn mixes_in_volume_over_100() {
+        let volume = SimulatedVolume::new(&[EdgeCase::Volume150]);
+        assert!(volume.current().volume > 100);
+
+        volume.set(200);
+        assert_eq!(volume.current().volume, 150);
+
+        let volume = SimulatedVolume::new(&[]);
+        volume.set(200);
+        assert_eq!(volume.current().volume, 100);
+    }
+
+    #[test]
+    fn mixes_in_a_scratchpad() {
+        let hyprland = SimulatedHyprland::new(&[EdgeCase::Scratchpad]);
+        assert!(workspace_ids(&hyprland).contains(&Desktop::SCRATCHPAD_ID));
+
+        let mut desktop = hyprland.desktop.lock().unwrap();
+        let shown = desktop.toggle_scratchpad();
+        assert!(matches!(shown[0].kind, WorkspaceEventKind::SpecialShown));
+        assert_eq!(
+            desktop.state().monitors[0].special_workspace,
+            Some(Desktop::SCRATCHPAD_ID)
+        );
+
+        assert!(!workspace_ids(&SimulatedHyprland::new(&[])).contains(&Desktop::SCRATCHPAD_ID));
+    }
+}
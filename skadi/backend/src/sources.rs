@@ -0,0 +1,340 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+use tokio::{task::JoinHandle, time::Instant};
+use ts_rs::TS;
+
+use crate::{
+    command::Command,
+    config::Config,
+    custom,
+    err::{BackendError, ErrorPayload},
+    hub::Emitter,
+    hyprland,
+    payload::{OpCode, PayloadData, Retain},
+    spotify, sysinfo, volume,
+};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+// A source that stayed up this long starts over from the initial backoff when it fails
+const STABLE_AFTER: Duration = Duration::from_secs(30);
+
+pub type SourceFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+#[derive(Debug, Serialize, Deserialize, TS, JsonSchema)]
+#[serde(
+    tag = "state",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum HealthStatus {
+    Running,
+    Restarting {
+        #[ts(type = "number")]
+        retry_in_ms: u64,
+    },
+    Failed,
+    Stopped,
+}
+
+#[derive(Debug, Serialize, Deserialize, TS, JsonSchema)]
+pub struct SourceHealth {
+    pub source: String,
+    pub status: HealthStatus,
+    pub restarts: u32,
+}
+
+impl PayloadData for SourceHealth {
+    fn op(&self) -> OpCode {
+        OpCode::Health
+    }
+
+    fn retain(&self) -> Retain {
+        Retain::Entry(self.source.clone())
+    }
+}
+
+// An integration the hub runs on demand, built from its config section
+pub trait EventSource: Send + Sync + 'static {
+    // `None` when the module is disabled. Sources that come in numbers, like custom commands,
+    // are built by their own registry function instead
+    fn from_config(_config: &Config) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        None
+    }
+
+    fn name(&self) -> &str;
+
+    // What it emits, it only runs while one of these has a subscriber
+    fn ops(&self) -> &'static [OpCode];
+
+    // Follows changes until the source fails, it is restarted with backoff by the supervisor
+    fn start(&self, emitter: Emitter) -> SourceFuture<'_, Result<(), BackendError>>;
+
+    // Runs after the task from `start` was aborted, for anything dropping it doesn't clean up
+    fn stop(&self) {}
+
+    // The cache key it retains its payloads under, for sources that share their opcodes with others.
+    // `None` when it has the opcodes to itself
+    fn cache_key(&self) -> Option<&str> {
+        None
+    }
+
+    // Emits the current state, called before every start so clients don't wait for the first change
+    fn snapshot(&self, _emitter: &Emitter) -> SourceFuture<'_, Result<(), BackendError>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn command(&self, _command: Command) -> SourceFuture<'_, Result<(), BackendError>> {
+        let name = self.name();
+        Box::pin(async move { Err(BackendError::UnsupportedCommand(name.to_string())) })
+    }
+}
+
+pub type Source = Arc<dyn EventSource>;
+
+// Modules can be left out of a build through their Cargo feature
+pub fn compiled_modules() -> Vec<&'static str> {
+    [
+        (sysinfo::MODULE, cfg!(feature = "sysinfo")),
+        (hyprland::MODULE, cfg!(feature = "hyprland")),
+        (spotify::MODULE, cfg!(feature = "spotify")),
+        (volume::MODULE, cfg!(feature = "volume")),
+        (custom::MODULE, cfg!(feature = "custom")),
+    ]
+    .into_iter()
+    .filter_map(|(module, compiled)| compiled.then_some(module))
+    .collect()
+}
+
+// Boxes a source for the registry, so it can be listed as a plain function
+pub fn register<S: EventSource>(config: &Config) -> Vec<Source> {
+    S::from_config(config)
+        .map(|source| Arc::new(source) as Source)
+        .into_iter()
+        .collect()
+}
+
+// Runs each source only while at least one client is subscribed to one of its opcodes
+pub struct Sources {
+    emitter: Emitter,
+    sources: Mutex<Vec<Source>>,
+    running: Mutex<HashMap<String, JoinHandle<()>>>,
+    // Set on shutdown, nothing starts after it
+    closed: AtomicBool,
+}
+
+impl Sources {
+    pub fn new(emitter: Emitter, sources: Vec<Source>) -> Arc<Self> {
+        Arc::new(Self {
+            emitter,
+            sources: Mutex::new(sources),
+            running: Mutex::default(),
+            closed: AtomicBool::new(false),
+        })
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.sources
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|source| source.name().to_string())
+            .collect()
+    }
+
+    pub fn subscribe(self: &Arc<Self>, ops: Vec<OpCode>) -> Subscription {
+        self.emitter.add_subscribers(&ops);
+        self.reconcile();
+
+        Subscription {
+            sources: self.clone(),
+            ops,
+        }
+    }
+
+    // Commands go to the module's current instance, whether or not it is running
+    pub async fn execute(&self, command: Command) -> Result<(), BackendError> {
+        let module = command.module();
+        let source = self
+            .sources
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|source| source.name() == module)
+            .cloned();
+
+        match source {
+            Some(source) => source.command(command).await,
+            None if !compiled_modules().contains(&module) => {
+                Err(BackendError::ModuleNotCompiled(module.to_string()))
+            }
+            None => Err(BackendError::ModuleDisabled(module.to_string())),
+        }
+    }
+
+    // Swaps in a new set of sources, the `changed` ones that are running restart with their new settings
+    pub fn replace(&self, sources: Vec<Source>, changed: &[String]) {
+        {
+            let mut current = self.sources.lock().unwrap();
+            let mut running = self.running.lock().unwrap();
+
+            for source in current.iter() {
+                let removed = !sources.iter().any(|new| new.name() == source.name());
+
+                if (removed || changed.iter().any(|name| name == source.name()))
+                    && let Some(handle) = running.remove(source.name())
+                {
+                    self.stop(source, &handle);
+                }
+            }
+
+            *current = sources;
+        }
+
+        self.reconcile();
+    }
+
+    // Stops every running source and waits until each task is gone. The programs they spawn are killed
+    // with their process groups and reaped as the tasks drop them, so none of them outlives the hub
+    pub async fn shutdown(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+
+        let stopped: Vec<(Source, JoinHandle<()>)> = {
+            let sources = self.sources.lock().unwrap();
+            let mut running = self.running.lock().unwrap();
+
+            sources
+                .iter()
+                .filter_map(|source| Some((source.clone(), running.remove(source.name())?)))
+                .collect()
+        };
+
+        for (source, handle) in stopped {
+            self.stop(&source, &handle);
+            let _ = handle.await;
+        }
+    }
+
+    fn reconcile(&self) {
+        if self.closed.load(Ordering::SeqCst) {
+            return;
+        }
+
+        let sources = self.sources.lock().unwrap();
+        let mut running = self.running.lock().unwrap();
+
+        for source in sources.iter() {
+            let wanted = source.ops().iter().any(|op| self.emitter.is_wanted(*op));
+
+            // A source that exited on its own gets another chance on the next subscription
+            let alive = running
+                .get(source.name())
+                .is_some_and(|handle| !handle.is_finished());
+
+            if wanted && !alive {
+                let handle = tokio::spawn(supervise(source.clone(), self.emitter.clone()));
+                running.insert(source.name().to_string(), handle);
+            } else if !wanted && let Some(handle) = running.remove(source.name()) {
+                self.stop(source, &handle);
+            }
+        }
+    }
+
+    fn stop(&self, source: &Source, handle: &JoinHandle<()>) {
+        handle.abort();
+        source.stop();
+        self.emitter.forget(source.ops(), source.cache_key());
+        self.emitter.emit(SourceHealth {
+            source: source.name().to_string(),
+            status: HealthStatus::Stopped,
+            restarts: 0,
+        });
+    }
+}
+
+// Sources are meant to run forever, so whenever one returns it is restarted after a growing delay,
+// unless the error says retrying won't help
+async fn supervise(source: Source, emitter: Emitter) {
+    let name = source.name();
+    let mut backoff = INITIAL_BACKOFF;
+    let mut restarts = 0;
+
+    loop {
+        emitter.emit(SourceHealth {
+            source: name.to_string(),
+            status: HealthStatus::Running,
+            restarts,
+        });
+
+        // A failed snapshot is reported, but following changes may still work
+        if let Err(e) = source.snapshot(&emitter).await {
+            emitter.error(name, e);
+        }
+
+        let started = Instant::now();
+        let error = match source.start(emitter.clone()).await {
+            Ok(()) => BackendError::SourceExited(name.to_string()),
+            Err(e) => e,
+        };
+
+        let retrying = error.is_retryable();
+
+        emitter.emit(ErrorPayload {
+            module: name.to_string(),
+            error,
+            retrying,
+        });
+
+        if !retrying {
+            emitter.emit(SourceHealth {
+                source: name.to_string(),
+                status: HealthStatus::Failed,
+                restarts,
+            });
+            return;
+        }
+
+        if started.elapsed() >= STABLE_AFTER {
+            backoff = INITIAL_BACKOFF;
+        }
+
+        emitter.emit(SourceHealth {
+            source: name.to_string(),
+            status: HealthStatus::Restarting {
+                retry_in_ms: backoff.as_millis() as u64,
+            },
+            restarts,
+        });
+
+        tokio::time::sleep(backoff).await;
+
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+        restarts += 1;
+    }
+}
+
+pub struct Subscription {
+    sources: Arc<Sources>,
+    ops: Vec<OpCode>,
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.sources.emitter.remove_subscribers(&self.ops);
+        self.sources.reconcile();
+    }
+}
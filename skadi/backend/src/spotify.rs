@@ -1,24 +1,45 @@
-use serde::Serialize;
-use std::process::Stdio;
-use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::process::Command;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
 
 use crate::payload::{OpCode, PayloadData};
 
-#[derive(Debug, Clone, Serialize)]
+#[cfg(feature = "spotify")]
+use {
+    crate::{
+        command::{self, Command, Process},
+        config::Config,
+        err::BackendError,
+        hub::Emitter,
+        sources::{EventSource, SourceFuture},
+    },
+    std::process::Stdio,
+    tokio::{
+        io::{AsyncBufReadExt, BufReader},
+        process,
+    },
+};
+
+pub const MODULE: &str = "spotify";
+pub const DEFAULT_PLAYER: &str = "spotify";
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct TrackInfo {
     pub title: Option<String>,
     pub artist: Option<String>,
     pub album: Option<String>,
     pub status: String,
+    #[ts(type = "number | null")]
     pub position: Option<u64>,
+    #[ts(type = "number | null")]
     pub duration: Option<u64>,
     pub volume: Option<String>,
     pub artwork_url: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS, JsonSchema)]
+#[ts(repr(enum = name))]
 pub enum SpotifyEventKind {
     Request,
     Playing,
@@ -40,7 +61,7 @@ impl TryFrom<&str> for SpotifyEventKind {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SpotifyEvent {
     #[serde(rename = "type")]
@@ -54,10 +75,92 @@ impl PayloadData for SpotifyEvent {
     }
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, TS, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum MediaAction {
+    Play,
+    Pause,
+    PlayPause,
+    Next,
+    Previous,
+}
+
+#[cfg(feature = "spotify")]
+impl MediaAction {
+    fn as_arg(&self) -> &'static str {
+        match self {
+            MediaAction::Play => "play",
+            MediaAction::Pause => "pause",
+            MediaAction::PlayPause => "play-pause",
+            MediaAction::Next => "next",
+            MediaAction::Previous => "previous",
+        }
+    }
+}
+
+#[cfg(feature = "spotify")]
+pub struct Spotify {
+    listener: PlayerctlListener,
+}
+
+#[cfg(feature = "spotify")]
+impl EventSource for Spotify {
+    fn from_config(config: &Config) -> Option<Self> {
+        config.spotify.enabled.then(|| Self {
+            listener: PlayerctlListener::new(&config.spotify.player),
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        MODULE
+    }
+
+    fn ops(&self) -> &'static [OpCode] {
+        &[OpCode::SpotifyEvent]
+    }
+
+    fn start(&self, emitter: Emitter) -> SourceFuture<'_, Result<(), BackendError>> {
+        Box::pin(async move {
+            self.listener
+                .listen_for_changes(|event| match event {
+                    Ok((e, info)) => emitter.emit(SpotifyEvent {
+                        kind: SpotifyEventKind::try_from(e).unwrap_or(SpotifyEventKind::Stopped),
+                        track_info: info,
+                    }),
+                    Err(e) => emitter.error(MODULE, e),
+                })
+                .await
+        })
+    }
+
+    fn snapshot(&self, emitter: &Emitter) -> SourceFuture<'_, Result<(), BackendError>> {
+        let emitter = emitter.clone();
+
+        Box::pin(async move {
+            emitter.emit(SpotifyEvent {
+                kind: SpotifyEventKind::Request,
+                track_info: self.listener.get_current_track().await?,
+            });
+            Ok(())
+        })
+    }
+
+    fn command(&self, command: Command) -> SourceFuture<'_, Result<(), BackendError>> {
+        Box::pin(async move {
+            match command {
+                Command::Media { action } => self.listener.control(action).await,
+                _ => Err(BackendError::UnsupportedCommand(MODULE.to_string())),
+            }
+        })
+    }
+}
+
+#[cfg(feature = "spotify")]
 pub struct PlayerctlListener {
     player: String,
 }
 
+#[cfg(feature = "spotify")]
 impl PlayerctlListener {
     pub fn new<P: AsRef<str>>(player: P) -> Self {
         Self {
@@ -65,7 +168,7 @@ impl PlayerctlListener {
         }
     }
 
-    pub async fn get_current_track(&self) -> Result<TrackInfo, Box<dyn std::error::Error>> {
+    pub async fn get_current_track(&self) -> Result<TrackInfo, BackendError> {
         let title = self.get_property("title").await.ok();
         let artist = self.get_property("artist").await.ok();
         let album = self.get_property("album").await.ok();
@@ -98,58 +201,59 @@ impl PlayerctlListener {
         })
     }
 
-    async fn get_property(&self, property: &str) -> Result<String, Box<dyn std::error::Error>> {
-        let output = Command::new("playerctl")
-            .args(["-p", &self.player, "metadata", property])
-            .output()
-            .await?;
+    pub async fn control(&self, action: MediaAction) -> Result<(), BackendError> {
+        command::run("playerctl", &["-p", &self.player, action.as_arg()]).await?;
+        Ok(())
+    }
 
-        if output.status.success() {
-            Ok(String::from_utf8(output.stdout)?.trim().to_string())
-        } else {
-            Err("Failed to get property".into())
-        }
+    async fn get_property(&self, property: &str) -> Result<String, BackendError> {
+        command::run("playerctl", &["-p", &self.player, "metadata", property]).await
     }
 
     // Listen for metadata changes with custom format
-    pub async fn listen_for_changes<F>(
-        &self,
-        mut callback: F,
-    ) -> Result<(), Box<dyn std::error::Error>>
+    pub async fn listen_for_changes<F>(&self, mut callback: F) -> Result<(), BackendError>
     where
-        F: FnMut(&str, TrackInfo),
+        F: FnMut(Result<(&str, TrackInfo), BackendError>),
     {
-        let mut child = Command::new("playerctl")
-            .args([
-                "-p", &self.player, 
-                "-f", "{{status}}|{{title}}|{{artist}}|{{album}}|{{position}}|{{mpris:length}}|{{volume}}|{{mpris:artUrl}}", 
-                "metadata", "--follow"
-            ])
-            .stdout(Stdio::piped())
-            .spawn()?;
-
-        let stdout = child.stdout.take().unwrap();
-        let reader = BufReader::new(stdout);
+        let mut playerctl = Process::spawn(
+            process::Command::new("playerctl")
+                .args([
+                    "-p", &self.player, 
+                    "-f", "{{status}}|{{title}}|{{artist}}|{{album}}|{{position}}|{{mpris:length}}|{{volume}}|{{mpris:artUrl}}", 
+                    "metadata", "--follow"
+                ])
+                .stdout(Stdio::piped()),
+        )?;
+
+        let reader = BufReader::new(playerctl.stdout());
         let mut lines = reader.lines();
 
         while let Some(line) = lines.next_line().await? {
-            if let Ok(track_info) = self.parse_playerctl_output(&line) {
-                let event_type = match track_info.status.as_str() {
-                    "Playing" => "playing",
-                    "Paused" => "paused",
-                    "Stopped" => "stopped",
-                    _ => "unknown",
-                };
-
-                callback(event_type, track_info);
-            }
+            let track_info = match self.parse_playerctl_output(&line) {
+                Ok(track_info) => track_info,
+                Err(e) => {
+                    callback(Err(e));
+                    continue;
+                }
+            };
+
+            let event_type = match track_info.status.as_str() {
+                "Playing" => "playing",
+                "Paused" => "paused",
+                "Stopped" => "stopped",
+                _ => "unknown",
+            };
+
+            callback(Ok((event_type, track_info)));
         }
 
-        let _ = child.wait().await;
-        Ok(())
+        let _ = playerctl.wait().await;
+        Err(BackendError::SourceExited("playerctl --follow".to_string()))
     }
 
-    fn parse_playerctl_output(&self, line: &str) -> Result<TrackInfo, Box<dyn std::error::Error>> {
+    fn parse_playerctl_output(&self, line: &str) -> Result<TrackInfo, BackendError> {
+        let invalid = || BackendError::InvalidOutput("playerctl".to_string(), line.to_string());
+
         let parts: Vec<&str> = line.split('|').collect();
 
         if parts.len() >= 8 {
@@ -173,12 +277,12 @@ impl PlayerctlListener {
                 position: if parts[4].is_empty() {
                     None
                 } else {
-                    Some(parts[4].parse::<u64>()?)
+                    Some(parts[4].parse::<u64>().map_err(|_| invalid())?)
                 },
                 duration: if parts[5].is_empty() {
                     None
                 } else {
-                    Some(parts[5].parse::<u64>()?)
+                    Some(parts[5].parse::<u64>().map_err(|_| invalid())?)
                 },
                 volume: if parts[6].is_empty() {
                     None
@@ -192,7 +296,7 @@ impl PlayerctlListener {
                 },
             })
         } else {
-            Err("Invalid playerctl output format".into())
+            Err(invalid())
         }
     }
 }
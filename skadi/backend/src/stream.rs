@@ -0,0 +1,91 @@
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, Lines},
+    net::{
+        UnixStream,
+        unix::{OwnedReadHalf, OwnedWriteHalf},
+    },
+};
+
+use crate::{
+    err::BackendError,
+    event::Event,
+    hub::ClientMessage,
+    payload::{OpCode, PROTOCOL_VERSION, Payload},
+};
+
+// Yields typed events from a hub connection, starting with its hello
+pub struct EventReader<R> {
+    lines: Lines<BufReader<R>>,
+    greeted: bool,
+}
+
+impl<R: AsyncRead + Unpin> EventReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: BufReader::new(reader).lines(),
+            greeted: false,
+        }
+    }
+
+    // `None` once the hub closed the connection
+    pub async fn next(&mut self) -> Result<Option<Payload<Event>>, BackendError> {
+        let Some(line) = self.lines.next_line().await? else {
+            return Ok(None);
+        };
+
+        // Checked before decoding, a hub on another protocol may send what this build can't read
+        if !self.greeted {
+            check_hello(&line)?;
+            self.greeted = true;
+        }
+
+        Payload::parse(&line).map(Some)
+    }
+}
+
+// Subscribes on a fresh connection, returning the events and the half to send commands on
+pub async fn subscribe(
+    stream: UnixStream,
+    ops: Option<Vec<OpCode>>,
+) -> Result<(EventReader<OwnedReadHalf>, OwnedWriteHalf), BackendError> {
+    let (read, mut write) = stream.into_split();
+
+    send_message(&mut write, &ClientMessage::Subscribe { ops }).await?;
+
+    Ok((EventReader::new(read), write))
+}
+
+pub async fn send_message<W: AsyncWrite + Unpin>(
+    write: &mut W,
+    message: &ClientMessage,
+) -> Result<(), BackendError> {
+    let mut line = serde_json::to_string(message).expect("Failed to serialize client message");
+    line.push('\n');
+    write.write_all(line.as_bytes()).await?;
+
+    Ok(())
+}
+
+fn check_hello(line: &str) -> Result<(), BackendError> {
+    let hello: serde_json::Value =
+        serde_json::from_str(line).map_err(|e| BackendError::InvalidMessage(e.to_string()))?;
+
+    if hello["op"] != u16::from(OpCode::Hello) {
+        return Err(BackendError::InvalidMessage(format!(
+            "Expected a hello, got {}",
+            line
+        )));
+    }
+
+    // An older hub may not carry a version at all
+    let found = hello["data"]["version"].as_u64().unwrap_or(0) as u16;
+
+    if found != PROTOCOL_VERSION {
+        return Err(BackendError::ProtocolMismatch {
+            expected: PROTOCOL_VERSION,
+            found,
+        });
+    }
+
+    Ok(())
+}
@@ -1,23 +1,53 @@
-use crate::payload::{OpCode, Payload, PayloadData};
-use serde::Serialize;
-use std::time::Duration;
-use sysinfo::{Disks, Networks, System};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
 
-#[derive(Serialize)]
+use crate::payload::{OpCode, PayloadData};
+
+#[cfg(feature = "sysinfo")]
+use {
+    crate::{
+        config::Config,
+        err::BackendError,
+        hub::Emitter,
+        sources::{EventSource, SourceFuture},
+    },
+    std::{
+        path::{Path, PathBuf},
+        time::Duration,
+    },
+    sysinfo::{Disks, Networks, System},
+};
+
+pub const MODULE: &str = "sysinfo";
+
+#[derive(Debug, Serialize, Deserialize, TS, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SysinfoPayload {
     pub cpu_usage: f32,
+    #[ts(type = "number")]
     pub memory_used: u64,
+    #[ts(type = "number")]
     pub memory_total: u64,
+    #[ts(type = "number")]
     pub memory_free: u64,
+    #[ts(type = "number")]
     pub swap_used: u64,
+    #[ts(type = "number")]
     pub swap_total: u64,
+    #[ts(type = "number")]
     pub network_rx: u64,
+    #[ts(type = "number")]
     pub network_tx: u64,
+    #[ts(type = "number")]
     pub disk_read: u64,
+    #[ts(type = "number")]
     pub disk_write: u64,
+    #[ts(type = "number")]
     pub disk_usage: u64,
+    #[ts(type = "number")]
     pub disk_total: u64,
+    #[ts(type = "number")]
     pub disk_free: u64,
 }
 
@@ -27,12 +57,45 @@ impl PayloadData for SysinfoPayload {
     }
 }
 
-pub async fn poll_sysinfo(interval: Duration) -> ! {
+#[cfg(feature = "sysinfo")]
+pub struct Sysinfo {
+    interval: Duration,
+    mount: PathBuf,
+}
+
+#[cfg(feature = "sysinfo")]
+impl EventSource for Sysinfo {
+    fn from_config(config: &Config) -> Option<Self> {
+        config.sysinfo.enabled.then(|| Self {
+            interval: config.sysinfo.interval(),
+            mount: config.sysinfo.mount.clone(),
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        MODULE
+    }
+
+    fn ops(&self) -> &'static [OpCode] {
+        &[OpCode::Sysinfo]
+    }
+
+    // The first tick fires right away, so there is no separate snapshot
+    fn start(&self, emitter: Emitter) -> SourceFuture<'_, Result<(), BackendError>> {
+        Box::pin(async move { poll_sysinfo(emitter, self.interval, &self.mount).await })
+    }
+}
+
+#[cfg(feature = "sysinfo")]
+async fn poll_sysinfo(emitter: Emitter, interval: Duration, mount: &Path) -> ! {
     let mut system = System::new_all();
     let mut networks = Networks::new();
     let mut disks = Disks::new();
     let mut interval = tokio::time::interval(interval);
 
+    // A missing mount is reported once rather than every tick, and again if it goes away later
+    let mut missing_reported = false;
+
     loop {
         interval.tick().await;
 
@@ -49,18 +112,26 @@ pub async fn poll_sysinfo(interval: Duration) -> ! {
         let mut disk_total = 0;
         let mut disk_free = 0;
 
-        // Find the root disk (/)
-        if let Some(root_disk) = disks
-            .iter()
-            .find(|disk| disk.mount_point().to_str() == Some("/"))
-        {
-            let usage = root_disk.usage();
-
-            disk_read = usage.read_bytes;
-            disk_write = usage.written_bytes;
-            disk_usage = root_disk.total_space() - root_disk.available_space();
-            disk_total = root_disk.total_space();
-            disk_free = root_disk.available_space();
+        match disks.iter().find(|disk| disk.mount_point() == mount) {
+            Some(disk) => {
+                let usage = disk.usage();
+
+                disk_read = usage.read_bytes;
+                disk_write = usage.written_bytes;
+                disk_usage = disk.total_space() - disk.available_space();
+                disk_total = disk.total_space();
+                disk_free = disk.available_space();
+                missing_reported = false;
+            }
+            None if !missing_reported => {
+                let error = BackendError::InvalidConfig(
+                    "sysinfo.mount".to_string(),
+                    format!("nothing is mounted at {}", mount.display()),
+                );
+                emitter.error(MODULE, error);
+                missing_reported = true;
+            }
+            None => {}
         }
 
         let payload = SysinfoPayload {
@@ -79,10 +150,6 @@ pub async fn poll_sysinfo(interval: Duration) -> ! {
             disk_free,
         };
 
-        println!(
-            "{}",
-            serde_json::to_string(&Payload::new(payload))
-                .expect("Failed to serialize sysinfo payload")
-        );
+        emitter.emit(payload);
     }
 }
@@ -1,10 +1,30 @@
-use crate::payload::{OpCode, Payload, PayloadData};
-use serde::Serialize;
-use std::process::Stdio;
-use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::process::Command;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
 
-#[derive(Debug, Clone, Serialize)]
+use crate::payload::{OpCode, PayloadData};
+
+#[cfg(feature = "volume")]
+use {
+    crate::{
+        command::{self, Command, Process},
+        config::Config,
+        err::BackendError,
+        hub::Emitter,
+        sources::{EventSource, SourceFuture},
+    },
+    std::process::Stdio,
+    tokio::{
+        io::{AsyncBufReadExt, BufReader},
+        process,
+    },
+};
+
+pub const MODULE: &str = "volume";
+
+pub const DEFAULT_SINK: &str = "@DEFAULT_SINK@";
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS, JsonSchema)]
 pub struct VolumeEvent {
     pub volume: u32,
 }
@@ -15,51 +35,121 @@ impl PayloadData for VolumeEvent {
     }
 }
 
-pub async fn monitor_volume_changes() -> Result<(), Box<dyn std::error::Error>> {
+#[cfg(feature = "volume")]
+pub struct Volume {
+    sink: String,
+}
+
+#[cfg(feature = "volume")]
+impl EventSource for Volume {
+    fn from_config(config: &Config) -> Option<Self> {
+        config.volume.enabled.then(|| Self {
+            sink: config.volume.sink.clone(),
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        MODULE
+    }
+
+    fn ops(&self) -> &'static [OpCode] {
+        &[OpCode::VolumeEvent]
+    }
+
+    fn start(&self, emitter: Emitter) -> SourceFuture<'_, Result<(), BackendError>> {
+        Box::pin(monitor_volume_changes(emitter, &self.sink))
+    }
+
+    fn snapshot(&self, emitter: &Emitter) -> SourceFuture<'_, Result<(), BackendError>> {
+        let emitter = emitter.clone();
+
+        Box::pin(async move {
+            let volume = get_current_volume(&self.sink).await?;
+            emitter.emit(VolumeEvent { volume });
+            Ok(())
+        })
+    }
+
+    fn command(&self, command: Command) -> SourceFuture<'_, Result<(), BackendError>> {
+        Box::pin(async move {
+            let sink = &self.sink;
+
+            match command {
+                Command::VolumeSet { volume } => set_volume(sink, volume).await,
+                Command::VolumeStep { delta } => step_volume(sink, delta).await,
+                Command::VolumeMute => toggle_mute(sink).await,
+                _ => Err(BackendError::UnsupportedCommand(MODULE.to_string())),
+            }
+        })
+    }
+}
+
+#[cfg(feature = "volume")]
+async fn monitor_volume_changes(emitter: Emitter, sink: &str) -> Result<(), BackendError> {
     // Subscribe to PulseAudio events
-    let mut child = Command::new("pactl")
-        .args(&["subscribe"])
-        .stdout(Stdio::piped())
-        .spawn()?;
+    let mut pactl = Process::spawn(
+        process::Command::new("pactl")
+            .args(["subscribe"])
+            .stdout(Stdio::piped()),
+    )?;
 
-    let stdout = child.stdout.take().unwrap();
-    let reader = BufReader::new(stdout);
+    let reader = BufReader::new(pactl.stdout());
     let mut lines = reader.lines();
 
     while let Some(line) = lines.next_line().await? {
         // Check if the event is related to sink (output device) changes
         if line.contains("'change' on sink") {
-            if let Ok(volume) = get_current_volume().await {
-                let event = VolumeEvent { volume };
-                let payload = Payload::new(event);
-
-                println!(
-                    "{}",
-                    serde_json::to_string(&payload).expect("Failed to serialize volume event")
-                );
-            }
+            emit_current_volume(&emitter, sink).await;
         }
     }
 
-    Ok(())
+    Err(BackendError::SourceExited("pactl subscribe".to_string()))
 }
 
-async fn get_current_volume() -> Result<u32, Box<dyn std::error::Error>> {
-    let output = Command::new("pactl")
-        .args(&["get-sink-volume", "@DEFAULT_SINK@"])
-        .output()
-        .await?;
+#[cfg(feature = "volume")]
+async fn emit_current_volume(emitter: &Emitter, sink: &str) {
+    match get_current_volume(sink).await {
+        Ok(volume) => emitter.emit(VolumeEvent { volume }),
+        Err(e) => emitter.error(MODULE, e),
+    }
+}
 
-    let output_str = String::from_utf8(output.stdout)?;
+#[cfg(feature = "volume")]
+async fn get_current_volume(sink: &str) -> Result<u32, BackendError> {
+    let output_str = command::run("pactl", &["get-sink-volume", sink]).await?;
 
     // Parse volume percentage from output like "Volume: front-left: 65536 /  100% / 0.00 dB"
     if let Some(percent_pos) = output_str.find('%') {
         let before_percent = &output_str[..percent_pos];
-        if let Some(space_pos) = before_percent.rfind(' ') {
-            let volume_str = &before_percent[space_pos + 1..];
-            return Ok(volume_str.parse()?);
+        if let Some(space_pos) = before_percent.rfind(' ')
+            && let Ok(volume) = before_percent[space_pos + 1..].parse()
+        {
+            return Ok(volume);
         }
     }
 
-    Err("Could not parse volume".into())
+    Err(BackendError::InvalidOutput(
+        "pactl get-sink-volume".to_string(),
+        output_str,
+    ))
+}
+
+#[cfg(feature = "volume")]
+async fn set_volume(sink: &str, volume: u32) -> Result<(), BackendError> {
+    let volume = format!("{}%", volume);
+    command::run("pactl", &["set-sink-volume", sink, &volume]).await?;
+    Ok(())
+}
+
+#[cfg(feature = "volume")]
+async fn step_volume(sink: &str, delta: i32) -> Result<(), BackendError> {
+    let delta = format!("{:+}%", delta);
+    command::run("pactl", &["set-sink-volume", sink, &delta]).await?;
+    Ok(())
+}
+
+#[cfg(feature = "volume")]
+async fn toggle_mute(sink: &str) -> Result<(), BackendError> {
+    command::run("pactl", &["set-sink-mute", sink, "toggle"]).await?;
+    Ok(())
 }
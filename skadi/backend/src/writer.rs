@@ -0,0 +1,216 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+use tokio::{
+    io::{AsyncWrite, AsyncWriteExt},
+    sync::Notify,
+};
+
+use crate::{err::BackendError, payload::OpCode};
+
+// Lines a client may fall behind by before the drop policy kicks in
+const CAPACITY: usize = 64;
+const BUFFER_SIZE: usize = 16 * 1024;
+
+pub type Event = (OpCode, Arc<str>);
+
+// Which events may be dropped for a slow consumer, lowest first. Sysinfo samples go before
+// state that a later event supersedes, and workspace deltas, Hyprland events, errors and replies
+// never go
+pub fn drop_rank(op: OpCode) -> Option<u8> {
+    match op {
+        OpCode::Sysinfo => Some(0),
+        OpCode::WindowChanged
+        | OpCode::SpotifyEvent
+        | OpCode::VolumeEvent
+        | OpCode::Health
+        | OpCode::Custom
+        | OpCode::WorkspaceState => Some(1),
+        OpCode::Error | OpCode::Workspace | OpCode::Hyprland | OpCode::Reply | OpCode::Hello => {
+            None
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Outbox {
+    queue: Mutex<VecDeque<Event>>,
+    notify: Notify,
+    closed: AtomicBool,
+}
+
+impl Outbox {
+    pub fn push(&self, op: OpCode, line: Arc<str>) {
+        let mut queue = self.queue.lock().unwrap();
+
+        if queue.len() >= CAPACITY && !make_room(&mut queue, op) {
+            return;
+        }
+
+        queue.push_back((op, line));
+        self.notify.notify_one();
+    }
+
+    // The writer sends what is still queued, then finishes
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        self.notify.notify_one();
+    }
+
+    // False once the outbox is closed and empty
+    async fn drain_into(&self, buffer: &mut Vec<u8>) -> bool {
+        loop {
+            {
+                let mut queue = self.queue.lock().unwrap();
+
+                if !queue.is_empty() {
+                    for (_, line) in queue.drain(..) {
+                        buffer.extend_from_slice(line.as_bytes());
+                        buffer.push(b'\n');
+                    }
+
+                    return true;
+                }
+
+                if self.closed.load(Ordering::SeqCst) {
+                    return false;
+                }
+            }
+
+            self.notify.notified().await;
+        }
+    }
+}
+
+// Evicts the oldest event of the lowest rank, returns false if the incoming one should go instead.
+// Events that must never be dropped are let through even over capacity
+fn make_room(queue: &mut VecDeque<Event>, incoming: OpCode) -> bool {
+    let victim = queue
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (op, _))| drop_rank(*op).map(|rank| (rank, i)))
+        .min();
+
+    match (victim, drop_rank(incoming)) {
+        (Some((rank, _)), Some(incoming_rank)) if incoming_rank < rank => false,
+        (Some((_, i)), _) => {
+            queue.remove(i);
+            true
+        }
+        (None, Some(_)) => false,
+        (None, None) => true,
+    }
+}
+
+// The single task writing to a client, whole batches at a time through one reused buffer.
+// Returns once the outbox is closed and everything in it was written
+pub async fn run<W: AsyncWrite + Unpin>(
+    outbox: Arc<Outbox>,
+    mut writer: W,
+) -> Result<(), BackendError> {
+    let mut buffer = Vec::with_capacity(BUFFER_SIZE);
+
+    loop {
+        if !outbox.drain_into(&mut buffer).await {
+            return Ok(());
+        }
+
+        writer.write_all(&buffer).await?;
+        writer.flush().await?;
+
+        buffer.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(ops: impl IntoIterator<Item = OpCode>) -> Outbox {
+        let outbox = Outbox::default();
+
+        for (i, op) in ops.into_iter().enumerate() {
+            outbox.push(op, Arc::from(i.to_string()));
+        }
+
+        outbox
+    }
+
+    fn queued(outbox: &Outbox) -> Vec<(OpCode, String)> {
+        let queue = outbox.queue.lock().unwrap();
+        queue
+            .iter()
+            .map(|(op, line)| (*op, line.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn evicts_sysinfo_samples_first() {
+        let ops = [OpCode::Health, OpCode::Sysinfo, OpCode::Sysinfo]
+            .into_iter()
+            .chain(std::iter::repeat_n(OpCode::VolumeEvent, CAPACITY - 3));
+        let outbox = filled(ops);
+
+        outbox.push(OpCode::SpotifyEvent, Arc::from("new"));
+        outbox.push(OpCode::Hyprland, Arc::from("newer"));
+
+        let queue = queued(&outbox);
+        assert_eq!(queue.len(), CAPACITY);
+        assert!(queue.iter().all(|(op, _)| *op != OpCode::Sysinfo));
+        assert_eq!(queue[0], (OpCode::Health, "0".to_string()));
+        assert_eq!(queue[CAPACITY - 1], (OpCode::Hyprland, "newer".to_string()));
+    }
+
+    #[test]
+    fn evicts_the_oldest_superseded_event_next() {
+        let ops = [OpCode::Workspace, OpCode::Health, OpCode::VolumeEvent]
+            .into_iter()
+            .chain(std::iter::repeat_n(OpCode::Error, CAPACITY - 3));
+        let outbox = filled(ops);
+
+        // Nothing ranks lower than an incoming sample, so that is what goes
+        outbox.push(OpCode::Sysinfo, Arc::from("sample"));
+        outbox.push(OpCode::WindowChanged, Arc::from("title"));
+
+        let queue = queued(&outbox);
+        assert_eq!(queue.len(), CAPACITY);
+        assert_eq!(queue[0], (OpCode::Workspace, "0".to_string()));
+        assert_eq!(queue[1], (OpCode::VolumeEvent, "2".to_string()));
+        assert_eq!(
+            queue[CAPACITY - 1],
+            (OpCode::WindowChanged, "title".to_string())
+        );
+        assert!(queue.iter().all(|(op, _)| *op != OpCode::Sysinfo));
+    }
+
+    #[test]
+    fn never_drops_workspace_deltas_or_errors() {
+        let ops = (0..CAPACITY).map(|i| {
+            if i % 2 == 0 {
+                OpCode::Workspace
+            } else {
+                OpCode::Error
+            }
+        });
+        let outbox = filled(ops);
+
+        outbox.push(OpCode::Sysinfo, Arc::from("sample"));
+        outbox.push(OpCode::Health, Arc::from("health"));
+        outbox.push(OpCode::Workspace, Arc::from("workspace"));
+        outbox.push(OpCode::Error, Arc::from("error"));
+
+        let queue = queued(&outbox);
+        assert_eq!(queue.len(), CAPACITY + 2);
+        assert!(
+            queue
+                .iter()
+                .all(|(op, _)| matches!(op, OpCode::Workspace | OpCode::Error))
+        );
+        assert_eq!(queue[CAPACITY].1, "workspace");
+        assert_eq!(queue[CAPACITY + 1].1, "error");
+    }
+}
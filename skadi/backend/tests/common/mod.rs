@@ -0,0 +1,362 @@
+// Runs the real hub against a fake Hyprland socket and stub executables, all under a temp directory.
+// Every test gets its own directory and hub, so they can run in parallel
+#![allow(dead_code)]
+
+use backend::{
+    command::CommandReply,
+    event::Event,
+    hub::ClientMessage,
+    payload::{OpCode, Payload},
+    stream::{self, EventReader, send_message},
+};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    env, fs,
+    os::unix::fs::PermissionsExt,
+    path::PathBuf,
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU32, Ordering},
+    },
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{
+        UnixListener, UnixStream,
+        unix::{OwnedReadHalf, OwnedWriteHalf},
+    },
+};
+
+pub const SIGNATURE: &str = "test";
+
+// Long enough for a restart after the initial backoff
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+static NEXT_ID: AtomicU32 = AtomicU32::new(0);
+
+pub struct Harness {
+    pub dir: PathBuf,
+    hub: Option<Child>,
+    env: Vec<(String, String)>,
+}
+
+impl Harness {
+    pub fn new() -> Self {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let dir = env::temp_dir().join(format!("skadi-{}-{}", std::process::id(), id));
+
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("bin")).unwrap();
+        fs::create_dir_all(dir.join("config")).unwrap();
+        fs::create_dir_all(dir.join("hypr").join(SIGNATURE)).unwrap();
+
+        // Stubs come first, the shell and coreutils they use after them
+        let path = format!("{}:/usr/bin:/bin", dir.join("bin").display());
+
+        Self {
+            env: vec![
+                ("PATH".to_string(), path),
+                ("XDG_RUNTIME_DIR".to_string(), dir.display().to_string()),
+                (
+                    "XDG_CONFIG_HOME".to_string(),
+                    dir.join("config").display().to_string(),
+                ),
+                (
+                    "HYPRLAND_INSTANCE_SIGNATURE".to_string(),
+                    SIGNATURE.to_string(),
+                ),
+            ],
+            dir,
+            hub: None,
+        }
+    }
+
+    pub fn without_env(mut self, name: &str) -> Self {
+        self.env.retain(|(key, _)| key != name);
+        self
+    }
+
+    // Writes an executable shell script to `PATH`, with `$STATE` pointing at the test's directory
+    pub fn stub(&self, program: &str, script: &str) {
+        let path = self.dir.join("bin").join(program);
+        let contents = format!("#!/bin/sh\nSTATE='{}'\n{}\n", self.dir.display(), script);
+
+        fs::write(&path, contents).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    // What a stub wrote to `$STATE/<name>`, one line per call by convention
+    pub fn read(&self, name: &str) -> String {
+        fs::read_to_string(self.dir.join(name)).unwrap_or_default()
+    }
+
+    pub fn write(&self, name: &str, contents: &str) {
+        fs::write(self.dir.join(name), contents).unwrap();
+    }
+
+    // Becomes `$XDG_CONFIG_HOME/skadi/backend.toml`, read when the hub starts
+    pub fn config(&self, contents: &str) {
+        let dir = self.dir.join("config").join("skadi");
+
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("backend.toml"), contents).unwrap();
+    }
+
+    pub fn hyprland(&self) -> FakeHyprland {
+        let path = self.dir.join("hypr").join(SIGNATURE).join(".socket2.sock");
+
+        FakeHyprland {
+            listener: UnixListener::bind(path).unwrap(),
+            stream: None,
+        }
+    }
+
+    pub fn hyprland_requests(&self, replies: &[(&str, &str)]) -> FakeRequests {
+        let path = self.dir.join("hypr").join(SIGNATURE).join(".socket.sock");
+        FakeRequests::bind(path, replies)
+    }
+
+    pub fn start(&mut self) {
+        let hub = Command::new(env!("CARGO_BIN_EXE_backend"))
+            .arg("serve")
+            .env_remove("HYPRLAND_INSTANCE_SIGNATURE")
+            .envs(self.env.iter().map(|(key, value)| (key, value)))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+
+        self.hub = Some(hub);
+    }
+
+    // Subscribes and checks the hello, which lists the enabled modules
+    pub async fn subscribe(&self, ops: &[OpCode]) -> Client {
+        let path = self.dir.join("skadi").join("backend.sock");
+        let stream = within(async {
+            loop {
+                match UnixStream::connect(&path).await {
+                    Ok(stream) => return stream,
+                    Err(_) => tokio::time::sleep(Duration::from_millis(20)).await,
+                }
+            }
+        })
+        .await;
+
+        let (events, write) = stream::subscribe(stream, Some(ops.to_vec())).await.unwrap();
+        let mut client = Client { events, write };
+
+        let hello = client.next().await;
+        assert_eq!(hello.op, u16::from(OpCode::Hello));
+
+        client
+    }
+}
+
+impl Harness {
+    // Sends the hub a signal by name, like `TERM`, and waits for it to exit
+    pub async fn signal(&mut self, signal: &str) -> ExitStatus {
+        let mut hub = self.hub.take().expect("Hub was never started");
+
+        let status = Command::new("kill")
+            .args([format!("-{}", signal), hub.id().to_string()])
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        within(async {
+            loop {
+                if let Some(status) = hub.try_wait().unwrap() {
+                    return status;
+                }
+
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+    }
+}
+
+// Whether a process a stub wrote its pid for is still around, a zombie counts as gone. Right for a
+// grandchild of the hub, which whatever it was reparented to reaps
+pub fn is_running(pid: &str) -> bool {
+    let Ok(stat) = fs::read_to_string(format!("/proc/{}/stat", pid.trim())) else {
+        return false;
+    };
+
+    // The state follows the parenthesized command name
+    stat.rsplit_once(") ")
+        .is_some_and(|(_, rest)| !rest.starts_with('Z'))
+}
+
+// Whether a child of the hub was reaped, a zombie means the hub never waited for it
+pub fn is_reaped(pid: &str) -> bool {
+    fs::metadata(format!("/proc/{}", pid.trim())).is_err()
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        if let Some(mut hub) = self.hub.take() {
+            let _ = hub.kill();
+            let _ = hub.wait();
+        }
+
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+pub struct FakeHyprland {
+    listener: UnixListener,
+    stream: Option<UnixStream>,
+}
+
+impl FakeHyprland {
+    // Waits for the backend to connect, again after a `disconnect`
+    pub async fn accept(&mut self) {
+        let (stream, _) = within(self.listener.accept()).await.unwrap();
+        self.stream = Some(stream);
+    }
+
+    pub async fn send(&mut self, line: &str) {
+        let stream = self
+            .stream
+            .as_mut()
+            .expect("Nothing connected to the fake Hyprland");
+        stream
+            .write_all(format!("{}\n", line).as_bytes())
+            .await
+            .unwrap();
+    }
+
+    pub fn disconnect(&mut self) {
+        self.stream = None;
+    }
+}
+
+// Answers each request on `.socket.sock` from a table, unknown ones like Hyprland does
+pub struct FakeRequests {
+    pub path: PathBuf,
+    replies: Arc<Mutex<HashMap<String, String>>>,
+    received: Arc<Mutex<Vec<String>>>,
+}
+
+impl FakeRequests {
+    fn bind(path: PathBuf, replies: &[(&str, &str)]) -> Self {
+        let listener = UnixListener::bind(&path).unwrap();
+        let fake = Self {
+            path,
+            replies: Arc::default(),
+            received: Arc::default(),
+        };
+
+        for (request, reply) in replies {
+            fake.set(request, reply);
+        }
+
+        let replies = fake.replies.clone();
+        let received = fake.received.clone();
+
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                // Hyprland takes whatever arrives in one read as the request
+                let mut buffer = [0; 4096];
+                let n = stream.read(&mut buffer).await.unwrap();
+                let request = String::from_utf8_lossy(&buffer[..n]).to_string();
+
+                let reply = replies
+                    .lock()
+                    .unwrap()
+                    .get(&request)
+                    .cloned()
+                    .unwrap_or_else(|| "unknown request".to_string());
+
+                received.lock().unwrap().push(request);
+                let _ = stream.write_all(reply.as_bytes()).await;
+            }
+        });
+
+        fake
+    }
+
+    // Replaces the reply to a request, for Hyprland's state changing
+    pub fn set(&self, request: &str, reply: &str) {
+        self.replies
+            .lock()
+            .unwrap()
+            .insert(request.to_string(), reply.to_string());
+    }
+
+    pub fn received(&self) -> Vec<String> {
+        self.received.lock().unwrap().clone()
+    }
+}
+
+pub struct Client {
+    events: EventReader<OwnedReadHalf>,
+    write: OwnedWriteHalf,
+}
+
+impl Client {
+    pub async fn next(&mut self) -> Payload<Event> {
+        within(self.events.next())
+            .await
+            .unwrap()
+            .expect("Hub closed the connection")
+    }
+
+    // `None` once the hub closed the connection
+    pub async fn try_next(&mut self) -> Option<Payload<Event>> {
+        within(self.events.next()).await.unwrap()
+    }
+
+    // Compares the opcode and the data exactly, the metadata differs every run
+    pub async fn expect(&mut self, op: OpCode, data: Value) {
+        let payload = self.next().await;
+        let found = serde_json::to_value(&payload.data).unwrap();
+
+        assert_eq!(
+            (OpCode::try_from(payload.op).unwrap(), &found),
+            (op, &data),
+            "Unexpected payload"
+        );
+    }
+
+    pub async fn expect_none(&mut self, wait: Duration) {
+        if let Ok(payload) = tokio::time::timeout(wait, self.events.next()).await {
+            panic!("Expected nothing, got {:?}", payload);
+        }
+    }
+
+    pub async fn command(&mut self, id: &str, command: Value) -> CommandReply {
+        let request =
+            serde_json::from_value(serde_json::json!({ "id": id, "command": command })).unwrap();
+        send_message(&mut self.write, &ClientMessage::Command(request))
+            .await
+            .unwrap();
+
+        loop {
+            if let Event::Reply(reply) = self.next().await.data
+                && reply.id.as_deref() == Some(id)
+            {
+                return reply;
+            }
+        }
+    }
+}
+
+pub fn health(source: &str, status: Value, restarts: u32) -> Value {
+    serde_json::json!({ "source": source, "status": status, "restarts": restarts })
+}
+
+pub fn error(module: &str, kind: &str, message: &str, retrying: bool) -> Value {
+    serde_json::json!({ "module": module, "kind": kind, "message": message, "retrying": retrying })
+}
+
+async fn within<F: Future>(future: F) -> F::Output {
+    tokio::time::timeout(TIMEOUT, future)
+        .await
+        .expect("Timed out waiting on the hub")
+}
@@ -0,0 +1,238 @@
+#![cfg(feature = "custom")]
+
+mod common;
+
+use backend::payload::OpCode;
+use common::{Harness, error, health, is_running};
+use serde_json::json;
+use std::time::Duration;
+
+const OPS: [OpCode; 3] = [OpCode::Error, OpCode::Health, OpCode::Custom];
+
+// Counts its runs, so every poll prints something new
+const UPTIME: &str = r#"
+runs=$(( $(cat "$STATE/runs" 2>/dev/null || echo 0) + 1 ))
+echo "$runs" > "$STATE/runs"
+echo "up $runs"
+"#;
+
+// Prints a reading, a line that isn't JSON and another reading, then stays running. The bad line is
+// reported while the command keeps being followed
+const WEATHER: &str = r#"
+echo '{"temp":21,"sky":"clear"}'
+echo 'offline'
+echo '{"temp":19,"sky":"rain"}'
+exec sleep 60
+"#;
+
+// Fails the first run the way a command without network does, succeeds after
+const UPDATES: &str = r#"
+if [ ! -e "$STATE/failed" ]; then
+    touch "$STATE/failed"
+    echo 'could not resolve host' >&2
+    exit 1
+fi
+echo 3
+"#;
+
+#[tokio::test]
+async fn wraps_polled_text_output() {
+    let mut harness = Harness::new();
+    harness.stub("uptime-stub", UPTIME);
+    harness.config("[custom.uptime]\ncommand = \"uptime-stub\"\ninterval = 1");
+    harness.start();
+
+    let mut client = harness.subscribe(&OPS).await;
+
+    client
+        .expect(
+            OpCode::Health,
+            health("uptime", json!({ "state": "running" }), 0),
+        )
+        .await;
+    client
+        .expect(
+            OpCode::Custom,
+            json!({ "source": "uptime", "value": "up 1" }),
+        )
+        .await;
+    client
+        .expect(
+            OpCode::Custom,
+            json!({ "source": "uptime", "value": "up 2" }),
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn follows_json_output_line_by_line() {
+    let mut harness = Harness::new();
+    harness.stub("weather", WEATHER);
+    harness.config("[custom.weather]\ncommand = \"weather\"\nformat = \"json\"");
+    harness.start();
+
+    let mut client = harness.subscribe(&OPS).await;
+
+    client
+        .expect(
+            OpCode::Health,
+            health("weather", json!({ "state": "running" }), 0),
+        )
+        .await;
+    client
+        .expect(
+            OpCode::Custom,
+            json!({ "source": "weather", "value": { "temp": 21, "sky": "clear" } }),
+        )
+        .await;
+    client
+        .expect(
+            OpCode::Error,
+            error(
+                "weather",
+                "invalidOutput",
+                "Unexpected output from weather: offline",
+                true,
+            ),
+        )
+        .await;
+    client
+        .expect(
+            OpCode::Custom,
+            json!({ "source": "weather", "value": { "temp": 19, "sky": "rain" } }),
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn restarts_a_failing_command() {
+    let mut harness = Harness::new();
+    harness.stub("updates", UPDATES);
+    harness.config("[custom.updates]\ncommand = \"updates\"\ninterval = 60");
+    harness.start();
+
+    let mut client = harness.subscribe(&OPS).await;
+
+    client
+        .expect(
+            OpCode::Health,
+            health("updates", json!({ "state": "running" }), 0),
+        )
+        .await;
+    client
+        .expect(
+            OpCode::Error,
+            error(
+                "updates",
+                "commandFailed",
+                "updates failed: could not resolve host",
+                true,
+            ),
+        )
+        .await;
+    client
+        .expect(
+            OpCode::Health,
+            health(
+                "updates",
+                json!({ "state": "restarting", "retryInMs": 1000 }),
+                0,
+            ),
+        )
+        .await;
+    client
+        .expect(
+            OpCode::Health,
+            health("updates", json!({ "state": "running" }), 1),
+        )
+        .await;
+    client
+        .expect(OpCode::Custom, json!({ "source": "updates", "value": "3" }))
+        .await;
+}
+
+#[tokio::test]
+async fn keeps_the_snapshot_of_sources_a_reload_leaves_alone() {
+    let mut harness = Harness::new();
+    let config = |weather: &str| {
+        format!(
+            "[custom.uptime]\ncommand = \"echo up\"\ninterval = 60\n\n\
+             [custom.weather]\ncommand = \"echo {}\"\ninterval = 60",
+            weather
+        )
+    };
+    harness.config(&config("sunny"));
+    harness.start();
+
+    let mut client = harness.subscribe(&[OpCode::Custom]).await;
+
+    let mut values = vec![
+        serde_json::to_value(client.next().await.data).unwrap(),
+        serde_json::to_value(client.next().await.data).unwrap(),
+    ];
+    values.sort_by_key(|value| value["source"].to_string());
+    assert_eq!(
+        values,
+        [
+            json!({ "source": "uptime", "value": "up" }),
+            json!({ "source": "weather", "value": "sunny" }),
+        ]
+    );
+
+    harness.config(&config("rainy"));
+    client
+        .expect(
+            OpCode::Custom,
+            json!({ "source": "weather", "value": "rainy" }),
+        )
+        .await;
+
+    // Joining after the reload, the untouched source is still in the snapshot
+    let mut late = harness.subscribe(&[OpCode::Custom]).await;
+    late.expect(OpCode::Custom, json!({ "source": "uptime", "value": "up" }))
+        .await;
+    late.expect(
+        OpCode::Custom,
+        json!({ "source": "weather", "value": "rainy" }),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn stops_the_whole_pipeline_on_reload() {
+    let mut harness = Harness::new();
+    harness.stub(
+        "tail-log",
+        r#"
+sleep 60 &
+echo $! > "$STATE/sleep.pid"
+echo started
+wait
+"#,
+    );
+    harness.config("[custom.log]\ncommand = \"tail-log | cat\"");
+    harness.start();
+
+    let mut client = harness.subscribe(&[OpCode::Custom]).await;
+    client
+        .expect(
+            OpCode::Custom,
+            json!({ "source": "log", "value": "started" }),
+        )
+        .await;
+
+    let sleep = harness.read("sleep.pid");
+    assert!(is_running(&sleep));
+
+    harness.config("");
+
+    for _ in 0..100 {
+        if !is_running(&sleep) {
+            return;
+        }
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    panic!("The command's background job outlived its source");
+}
@@ -0,0 +1,123 @@
+#![cfg(feature = "hyprland")]
+
+mod common;
+
+use backend::hyprctl::Hyprctl;
+use common::Harness;
+
+// Trimmed from what Hyprland 0.45 answers, unknown fields have to be ignored
+const WORKSPACE: &str = r#"{
+    "id": 1, "name": "1", "monitor": "DP-1", "monitorID": 0, "windows": 2, "hasfullscreen": false,
+    "lastwindow": "0x55d1c6c0e2a0", "lastwindowtitle": "foot", "ispersistent": false
+}"#;
+
+const SPECIAL_WORKSPACE: &str = r#"{
+    "id": -98, "name": "special:magic", "monitor": "eDP-1", "monitorID": 1, "windows": 1,
+    "hasfullscreen": false, "lastwindow": "0x0", "lastwindowtitle": "", "ispersistent": false
+}"#;
+
+const CLIENT: &str = r#"{
+    "address": "0x55d1c6c0e2a0", "mapped": true, "hidden": false, "at": [10, 52], "size": [1900, 1018],
+    "workspace": { "id": 1, "name": "1" }, "floating": false, "pseudo": false, "monitor": 0,
+    "class": "foot", "title": "~/src", "initialClass": "foot", "initialTitle": "foot", "pid": 4242,
+    "xwayland": false, "pinned": false, "fullscreen": 0, "fullscreenClient": 0, "grouped": [],
+    "tags": [], "swallowing": "0x0", "focusHistoryID": 0
+}"#;
+
+const MONITORS: &str = r#"[{
+    "id": 0, "name": "DP-1", "description": "Dell Inc. DELL U2720Q", "make": "Dell Inc.",
+    "model": "DELL U2720Q", "serial": "", "width": 3840, "height": 2160, "refreshRate": 59.99700,
+    "x": 0, "y": 0, "activeWorkspace": { "id": 1, "name": "1" },
+    "specialWorkspace": { "id": 0, "name": "" }, "reserved": [0, 44, 0, 0], "scale": 1.50,
+    "transform": 0, "focused": true, "dpmsStatus": true, "vrr": false
+}]"#;
+
+#[tokio::test]
+async fn parses_query_replies() {
+    let harness = Harness::new();
+    let requests = harness.hyprland_requests(&[
+        (
+            "j/workspaces",
+            &format!("[{}, {}]", WORKSPACE, SPECIAL_WORKSPACE),
+        ),
+        ("j/activeworkspace", WORKSPACE),
+        ("j/clients", &format!("[{}]", CLIENT)),
+        ("j/monitors", MONITORS),
+        ("j/activewindow", CLIENT),
+    ]);
+    let hyprctl = Hyprctl::at(requests.path.clone());
+
+    let workspaces = hyprctl.workspaces().await.unwrap();
+    assert_eq!(workspaces.len(), 2);
+    assert_eq!(
+        (workspaces[1].id, workspaces[1].name.as_str()),
+        (-98, "special:magic")
+    );
+    assert_eq!(workspaces[0].last_window_title, "foot");
+
+    let active = hyprctl.active_workspace().await.unwrap();
+    assert_eq!((active.id, active.monitor.as_str()), (1, "DP-1"));
+
+    let clients = hyprctl.clients().await.unwrap();
+    assert_eq!(clients[0].initial_class, "foot");
+    assert_eq!(clients[0].size, [1900, 1018]);
+
+    let monitors = hyprctl.monitors().await.unwrap();
+    assert_eq!(monitors[0].special_workspace.id, 0);
+    assert!(monitors[0].focused && !monitors[0].disabled);
+
+    let window = hyprctl.active_window().await.unwrap().unwrap();
+    assert_eq!((window.title.as_str(), window.workspace.id), ("~/src", 1));
+}
+
+#[tokio::test]
+async fn reports_no_active_window() {
+    let harness = Harness::new();
+    let requests = harness.hyprland_requests(&[("j/activewindow", "{}")]);
+    let hyprctl = Hyprctl::at(requests.path.clone());
+
+    assert!(hyprctl.active_window().await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn turns_refusals_and_bad_replies_into_errors() {
+    let harness = Harness::new();
+    let requests = harness.hyprland_requests(&[
+        ("/keyword general:gaps_in 5", "ok"),
+        (
+            "/keyword general:nope 5",
+            "config option <general:nope> does not exist.",
+        ),
+        ("j/monitors", r#"[{ "id": 0 }]"#),
+    ]);
+    let hyprctl = Hyprctl::at(requests.path.clone());
+
+    hyprctl.keyword("general:gaps_in", "5").await.unwrap();
+
+    let error = hyprctl.keyword("general:nope", "5").await.unwrap_err();
+    assert_eq!(error.kind(), "hyprlandRequest");
+    assert_eq!(
+        error.to_string(),
+        "Hyprland refused keyword general:nope 5: config option <general:nope> does not exist."
+    );
+
+    let error = hyprctl.monitors().await.unwrap_err();
+    assert_eq!(error.kind(), "invalidOutput");
+
+    // Not in the table, so answered like an unknown request
+    let error = hyprctl.clients().await.unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Hyprland refused j/clients: unknown request"
+    );
+
+    assert_eq!(
+        requests.received(),
+        [
+            "/keyword general:gaps_in 5",
+            "/keyword general:nope 5",
+            "j/monitors",
+            "j/clients"
+        ]
+    );
+}
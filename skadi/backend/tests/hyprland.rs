@@ -0,0 +1,682 @@
+#![cfg(feature = "hyprland")]
+
+mod common;
+
+use backend::{event::Event, payload::OpCode};
+use common::{Client, FakeRequests, Harness, error, health};
+use serde_json::json;
+use std::time::Duration;
+
+const OPS: [OpCode; 4] = [
+    OpCode::Error,
+    OpCode::Health,
+    OpCode::Workspace,
+    OpCode::WindowChanged,
+];
+
+const MONITORS: &str = r#"[{
+    "id": 0, "name": "DP-1", "description": "", "width": 2560, "height": 1440, "refreshRate": 144.0,
+    "x": 0, "y": 0, "activeWorkspace": { "id": 2, "name": "2" },
+    "specialWorkspace": { "id": 0, "name": "" }, "scale": 1.0, "focused": true
+}]"#;
+
+const WORKSPACES: &str = r#"[{
+    "id": 2, "name": "2", "monitor": "DP-1", "monitorID": 0, "windows": 0, "hasfullscreen": false,
+    "lastwindow": "0x0", "lastwindowtitle": ""
+}, {
+    "id": 1, "name": "1", "monitor": "DP-1", "monitorID": 0, "windows": 1, "hasfullscreen": false,
+    "lastwindow": "0xa1", "lastwindowtitle": "foot"
+}]"#;
+
+// What the state queries answer while workspaces 1 and 2 exist and nothing has focus
+fn requests(harness: &Harness) -> FakeRequests {
+    harness.hyprland_requests(&[
+        ("j/workspaces", WORKSPACES),
+        ("j/monitors", MONITORS),
+        ("j/activewindow", "{}"),
+    ])
+}
+
+// `j/monitors` for the given monitors and the workspace active on each, the first one focused
+fn monitors(active: &[(&str, i32)]) -> String {
+    let monitors: Vec<String> = active
+        .iter()
+        .enumerate()
+        .map(|(i, (name, workspace))| {
+            format!(
+                r#"{{
+                    "id": {i}, "name": "{name}", "description": "", "width": 1920, "height": 1080,
+                    "refreshRate": 60.0, "x": 0, "y": 0,
+                    "activeWorkspace": {{ "id": {workspace}, "name": "{workspace}" }},
+                    "specialWorkspace": {{ "id": 0, "name": "" }}, "scale": 1.0, "focused": {}
+                }}"#,
+                i == 0
+            )
+        })
+        .collect();
+
+    format!("[{}]", monitors.join(","))
+}
+
+// One entry of `j/workspaces`
+fn workspace(id: i32, name: &str, monitor: &str) -> String {
+    format!(
+        r#"{{
+            "id": {id}, "name": "{name}", "monitor": "{monitor}", "monitorID": 0, "windows": 0,
+            "hasfullscreen": false, "lastwindow": "0x0", "lastwindowtitle": ""
+        }}"#
+    )
+}
+
+// `j/workspaces` for the given workspaces and the monitor each one is on
+fn workspaces(workspaces: &[(i32, &str)]) -> String {
+    let workspaces: Vec<String> = workspaces
+        .iter()
+        .map(|(id, monitor)| workspace(*id, &id.to_string(), monitor))
+        .collect();
+
+    format!("[{}]", workspaces.join(","))
+}
+
+// Waits for the state queried after an event, so the next one is handled with what it reported
+async fn settled(client: &mut Client) {
+    let payload = client.next().await;
+    assert!(
+        matches!(payload.data, Event::WorkspaceState(_)),
+        "Expected the state, got {:?}",
+        payload
+    );
+}
+
+#[tokio::test]
+async fn maps_socket_events_to_payloads() {
+    let mut harness = Harness::new();
+    let mut hyprland = harness.hyprland();
+    let _requests = requests(&harness);
+    harness.start();
+
+    let mut client = harness.subscribe(&OPS).await;
+    client
+        .expect(
+            OpCode::Health,
+            health("hyprland", json!({ "state": "running" }), 0),
+        )
+        .await;
+    hyprland.accept().await;
+
+    hyprland.send("workspace>>2").await;
+    hyprland.send("workspacev2>>2,2").await;
+    hyprland.send("createworkspace>>4").await;
+    hyprland.send("createworkspacev2>>4,4").await;
+    hyprland.send("destroyworkspace>>4").await;
+    hyprland.send("destroyworkspacev2>>4,4").await;
+    hyprland.send("activewindow>>foot,~/src").await;
+    hyprland.send("openwindow>>5a1b,2,foot,~").await;
+    hyprland.send("not an event").await;
+    hyprland.send("workspacev2>>abc").await;
+
+    client
+        .expect(
+            OpCode::Workspace,
+            json!({ "type": "Moved", "id": 2, "name": "2", "monitor": "DP-1" }),
+        )
+        .await;
+    client
+        .expect(
+            OpCode::Workspace,
+            json!({ "type": "Created", "id": 4, "name": "4", "monitor": "DP-1" }),
+        )
+        .await;
+    client
+        .expect(
+            OpCode::Workspace,
+            json!({ "type": "Destroyed", "id": 4, "name": "4", "monitor": "DP-1" }),
+        )
+        .await;
+    client
+        .expect(OpCode::WindowChanged, json!({ "title": "~/src" }))
+        .await;
+    client
+        .expect(
+            OpCode::Error,
+            error(
+                "hyprland",
+                "invalidOutput",
+                "Unexpected output from Hyprland: workspacev2>>abc",
+                true,
+            ),
+        )
+        .await;
+    client.expect_none(Duration::from_millis(200)).await;
+}
+
+#[tokio::test]
+async fn passes_on_every_event_typed() {
+    let mut harness = Harness::new();
+    let mut hyprland = harness.hyprland();
+    let _requests = requests(&harness);
+    harness.start();
+
+    let mut client = harness.subscribe(&[OpCode::Error, OpCode::Hyprland]).await;
+    hyprland.accept().await;
+
+    hyprland.send("openwindow>>5a1b,2,foot,~/src, again").await;
+    hyprland.send("movewindowv2>>5a1b,3,3").await;
+    hyprland.send("activewindowv2>>").await;
+    hyprland.send("fullscreen>>x").await;
+    hyprland.send("somethingnew>>a,b").await;
+
+    client
+        .expect(
+            OpCode::Hyprland,
+            json!({ "event": "openwindow", "address": "0x5a1b", "workspace": "2", "class": "foot", "title": "~/src, again" }),
+        )
+        .await;
+    client
+        .expect(
+            OpCode::Hyprland,
+            json!({ "event": "movewindowv2", "address": "0x5a1b", "workspaceId": 3, "workspace": "3" }),
+        )
+        .await;
+    client
+        .expect(
+            OpCode::Hyprland,
+            json!({ "event": "activewindowv2", "address": null }),
+        )
+        .await;
+    client
+        .expect(
+            OpCode::Error,
+            error(
+                "hyprland",
+                "invalidOutput",
+                "Unexpected output from Hyprland: fullscreen>>x",
+                true,
+            ),
+        )
+        .await;
+    client
+        .expect(
+            OpCode::Hyprland,
+            json!({ "event": "other", "name": "somethingnew", "data": "a,b" }),
+        )
+        .await;
+    client.expect_none(Duration::from_millis(200)).await;
+}
+
+#[tokio::test]
+async fn tracks_workspaces_per_monitor() {
+    let mut harness = Harness::new();
+    let mut hyprland = harness.hyprland();
+    let requests = harness.hyprland_requests(&[
+        (
+            "j/workspaces",
+            &workspaces(&[(1, "DP-1"), (2, "DP-1"), (5, "eDP-1")]),
+        ),
+        ("j/monitors", &monitors(&[("DP-1", 2), ("eDP-1", 5)])),
+        ("j/activewindow", "{}"),
+    ]);
+    harness.start();
+
+    let mut client = harness
+        .subscribe(&[OpCode::Error, OpCode::Workspace, OpCode::WorkspaceState])
+        .await;
+    hyprland.accept().await;
+    settled(&mut client).await;
+
+    requests.set("j/monitors", &monitors(&[("eDP-1", 5), ("DP-1", 2)]));
+    hyprland.send("focusedmon>>eDP-1,5").await;
+    hyprland.send("focusedmonv2>>eDP-1,5").await;
+    client
+        .expect(
+            OpCode::Workspace,
+            json!({ "type": "Focused", "id": 5, "name": "5", "monitor": "eDP-1" }),
+        )
+        .await;
+    settled(&mut client).await;
+
+    // New workspaces open on the focused monitor
+    requests.set(
+        "j/workspaces",
+        &workspaces(&[(1, "DP-1"), (2, "DP-1"), (5, "eDP-1"), (6, "eDP-1")]),
+    );
+    hyprland.send("createworkspace>>6").await;
+    hyprland.send("createworkspacev2>>6,6").await;
+    client
+        .expect(
+            OpCode::Workspace,
+            json!({ "type": "Created", "id": 6, "name": "6", "monitor": "eDP-1" }),
+        )
+        .await;
+    settled(&mut client).await;
+
+    requests.set("j/monitors", &monitors(&[("eDP-1", 6), ("DP-1", 2)]));
+    hyprland.send("workspace>>6").await;
+    hyprland.send("workspacev2>>6,6").await;
+    client
+        .expect(
+            OpCode::Workspace,
+            json!({ "type": "Moved", "id": 6, "name": "6", "monitor": "eDP-1" }),
+        )
+        .await;
+    settled(&mut client).await;
+
+    // Switching to a workspace on the other monitor moves focus along
+    requests.set("j/monitors", &monitors(&[("DP-1", 1), ("eDP-1", 6)]));
+    hyprland.send("workspace>>1").await;
+    hyprland.send("workspacev2>>1,1").await;
+    client
+        .expect(
+            OpCode::Workspace,
+            json!({ "type": "Moved", "id": 1, "name": "1", "monitor": "DP-1" }),
+        )
+        .await;
+    settled(&mut client).await;
+
+    requests.set(
+        "j/workspaces",
+        &workspaces(&[(1, "DP-1"), (2, "DP-1"), (5, "eDP-1"), (6, "DP-1")]),
+    );
+    requests.set("j/monitors", &monitors(&[("DP-1", 1), ("eDP-1", 5)]));
+    hyprland.send("moveworkspace>>6,DP-1").await;
+    hyprland.send("moveworkspacev2>>6,6,DP-1").await;
+    settled(&mut client).await;
+    client
+        .expect(
+            OpCode::Workspace,
+            json!({ "type": "Reassigned", "id": 6, "name": "6", "monitor": "DP-1" }),
+        )
+        .await;
+
+    requests.set(
+        "j/workspaces",
+        &workspaces(&[(1, "DP-1"), (2, "DP-1"), (5, "DP-1"), (6, "DP-1")]),
+    );
+    requests.set("j/monitors", &monitors(&[("DP-1", 1)]));
+    hyprland.send("monitorremoved>>eDP-1").await;
+    client
+        .expect(
+            OpCode::Workspace,
+            json!({ "type": "MonitorRemoved", "id": 5, "name": "5", "monitor": "eDP-1" }),
+        )
+        .await;
+    settled(&mut client).await;
+
+    hyprland.send("destroyworkspace>>5").await;
+    hyprland.send("destroyworkspacev2>>5,5").await;
+    client
+        .expect(
+            OpCode::Workspace,
+            json!({ "type": "Destroyed", "id": 5, "name": "5", "monitor": "DP-1" }),
+        )
+        .await;
+    client.expect_none(Duration::from_millis(200)).await;
+}
+
+#[tokio::test]
+async fn names_named_and_special_workspaces() {
+    let mut harness = Harness::new();
+    let mut hyprland = harness.hyprland();
+    let scratchpad = workspace(-98, "special:magic", "DP-1");
+    let requests = harness.hyprland_requests(&[
+        (
+            "j/workspaces",
+            &format!("[{}, {}]", scratchpad, workspace(1, "1", "DP-1")),
+        ),
+        ("j/monitors", &monitors(&[("DP-1", 1)])),
+        ("j/activewindow", "{}"),
+    ]);
+    harness.start();
+
+    let mut client = harness
+        .subscribe(&[OpCode::Error, OpCode::Workspace, OpCode::WorkspaceState])
+        .await;
+    hyprland.accept().await;
+    settled(&mut client).await;
+
+    let notes = |name| {
+        format!(
+            "[{}, {}, {}]",
+            workspace(-1337, name, "DP-1"),
+            scratchpad,
+            workspace(1, "1", "DP-1")
+        )
+    };
+
+    requests.set("j/workspaces", &notes("notes"));
+    hyprland.send("createworkspace>>notes").await;
+    hyprland.send("createworkspacev2>>-1337,notes").await;
+    client
+        .expect(
+            OpCode::Workspace,
+            json!({ "type": "Created", "id": -1337, "name": "notes", "monitor": "DP-1" }),
+        )
+        .await;
+    settled(&mut client).await;
+
+    requests.set(
+        "j/monitors",
+        &monitors(&[("DP-1", 1)]).replace(
+            r#""specialWorkspace": { "id": 0, "name": "" }"#,
+            r#""specialWorkspace": { "id": -98, "name": "special:magic" }"#,
+        ),
+    );
+    hyprland.send("activespecial>>special:magic,DP-1").await;
+    hyprland
+        .send("activespecialv2>>-98,special:magic,DP-1")
+        .await;
+    client
+        .expect(
+            OpCode::Workspace,
+            json!({ "type": "SpecialShown", "id": -98, "name": "special:magic", "monitor": "DP-1" }),
+        )
+        .await;
+    settled(&mut client).await;
+
+    requests.set("j/monitors", &monitors(&[("DP-1", 1)]));
+    hyprland.send("activespecial>>,DP-1").await;
+    hyprland.send("activespecialv2>>,,DP-1").await;
+    client
+        .expect(
+            OpCode::Workspace,
+            json!({ "type": "SpecialHidden", "id": -98, "name": "special:magic", "monitor": "DP-1" }),
+        )
+        .await;
+    settled(&mut client).await;
+
+    requests.set("j/workspaces", &notes("todo"));
+    hyprland.send("renameworkspace>>-1337,todo").await;
+    client
+        .expect(
+            OpCode::Workspace,
+            json!({ "type": "Renamed", "id": -1337, "name": "todo", "monitor": "DP-1" }),
+        )
+        .await;
+    settled(&mut client).await;
+
+    // Only named in the `v1` event, the id comes from the `v2` one
+    requests.set("j/monitors", &monitors(&[("DP-1", -1337)]));
+    hyprland.send("focusedmon>>DP-1,todo").await;
+    hyprland.send("focusedmonv2>>DP-1,-1337").await;
+    client
+        .expect(
+            OpCode::Workspace,
+            json!({ "type": "Focused", "id": -1337, "name": "todo", "monitor": "DP-1" }),
+        )
+        .await;
+    settled(&mut client).await;
+    client.expect_none(Duration::from_millis(200)).await;
+}
+
+#[tokio::test]
+async fn reconnects_after_the_socket_closes() {
+    let mut harness = Harness::new();
+    let mut hyprland = harness.hyprland();
+    let _requests = requests(&harness);
+    harness.start();
+
+    let mut client = harness.subscribe(&OPS).await;
+    client
+        .expect(
+            OpCode::Health,
+            health("hyprland", json!({ "state": "running" }), 0),
+        )
+        .await;
+    hyprland.accept().await;
+    hyprland.disconnect();
+
+    client
+        .expect(
+            OpCode::Error,
+            error(
+                "hyprland",
+                "sourceExited",
+                "Hyprland event socket exited",
+                true,
+            ),
+        )
+        .await;
+    client
+        .expect(
+            OpCode::Health,
+            health(
+                "hyprland",
+                json!({ "state": "restarting", "retryInMs": 1000 }),
+                0,
+            ),
+        )
+        .await;
+    client
+        .expect(
+            OpCode::Health,
+            health("hyprland", json!({ "state": "running" }), 1),
+        )
+        .await;
+
+    hyprland.accept().await;
+    hyprland.send("workspace>>3").await;
+    hyprland.send("workspacev2>>3,3").await;
+
+    client
+        .expect(
+            OpCode::Workspace,
+            json!({ "type": "Moved", "id": 3, "name": "3", "monitor": "DP-1" }),
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn gives_up_without_a_signature() {
+    let mut harness = Harness::new().without_env("HYPRLAND_INSTANCE_SIGNATURE");
+    harness.start();
+
+    let mut client = harness.subscribe(&OPS).await;
+    client
+        .expect(
+            OpCode::Health,
+            health("hyprland", json!({ "state": "running" }), 0),
+        )
+        .await;
+    client
+        .expect(
+            OpCode::Error,
+            error(
+                "hyprland",
+                "hyprlandSignatureNotSet",
+                "HYPRLAND_INSTANCE_SIGNATURE environment variable is not set",
+                false,
+            ),
+        )
+        .await;
+    client
+        .expect(
+            OpCode::Health,
+            health("hyprland", json!({ "state": "failed" }), 0),
+        )
+        .await;
+    client.expect_none(Duration::from_millis(200)).await;
+}
+
+#[tokio::test]
+async fn dispatches_workspace_commands() {
+    let mut harness = Harness::new();
+    let _hyprland = harness.hyprland();
+    let requests = requests(&harness);
+    requests.set("/dispatch workspace 3", "ok");
+    requests.set("/dispatch workspace 99", "Invalid workspace");
+    harness.start();
+
+    let mut client = harness.subscribe(&[OpCode::Reply]).await;
+    let reply = client
+        .command("1", json!({ "type": "workspace", "id": 3 }))
+        .await;
+    assert!(reply.ok, "{:?}", reply.error);
+
+    let reply = client
+        .command("2", json!({ "type": "workspace", "id": 99 }))
+        .await;
+    assert_eq!(
+        serde_json::to_value(&reply).unwrap(),
+        json!({
+            "id": "2",
+            "ok": false,
+            "error": {
+                "kind": "hyprlandRequest",
+                "message": "Hyprland refused dispatch workspace 99: Invalid workspace",
+            },
+        })
+    );
+
+    assert_eq!(
+        requests.received(),
+        ["/dispatch workspace 3", "/dispatch workspace 99"]
+    );
+}
+
+#[tokio::test]
+async fn dispatches_named_and_special_workspaces_by_name() {
+    let mut harness = Harness::new();
+    let _hyprland = harness.hyprland();
+    let requests = requests(&harness);
+    requests.set(
+        "j/workspaces",
+        &format!(
+            "[{}, {}]",
+            workspace(-1337, "chat", "DP-1"),
+            workspace(-98, "special:magic", "DP-1")
+        ),
+    );
+    requests.set("/dispatch workspace name:chat", "ok");
+    requests.set("/dispatch togglespecialworkspace magic", "ok");
+    harness.start();
+
+    let mut client = harness.subscribe(&[OpCode::Reply]).await;
+    for (id, workspace) in [("1", -1337), ("2", -98)] {
+        let reply = client
+            .command(id, json!({ "type": "workspace", "id": workspace }))
+            .await;
+        assert!(reply.ok, "{:?}", reply.error);
+    }
+
+    let reply = client
+        .command("3", json!({ "type": "workspace", "id": -5 }))
+        .await;
+    let error = reply.error.unwrap();
+    assert_eq!(
+        (error.kind(), error.to_string()),
+        (
+            "invalidMessage",
+            "Invalid message: No workspace -5".to_string()
+        )
+    );
+
+    assert_eq!(
+        requests.received(),
+        [
+            "j/workspaces",
+            "/dispatch workspace name:chat",
+            "j/workspaces",
+            "/dispatch togglespecialworkspace magic",
+            "j/workspaces"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn emits_state_on_connect_and_when_it_changes() {
+    let mut harness = Harness::new();
+    let mut hyprland = harness.hyprland();
+    let requests = requests(&harness);
+    harness.start();
+
+    let mut client = harness
+        .subscribe(&[OpCode::WindowChanged, OpCode::WorkspaceState])
+        .await;
+    hyprland.accept().await;
+
+    client
+        .expect(
+            OpCode::WorkspaceState,
+            json!({
+                "workspaces": [
+                    { "id": 1, "name": "1", "monitor": "DP-1", "windows": 1 },
+                    { "id": 2, "name": "2", "monitor": "DP-1", "windows": 0 },
+                ],
+                "monitors": [{ "name": "DP-1", "activeWorkspace": 2, "specialWorkspace": null, "focused": true }],
+                "focusedWindow": null,
+            }),
+        )
+        .await;
+
+    // Nothing was focused before, so only the event itself comes through
+    hyprland.send("activewindow>>,").await;
+    hyprland.send("activewindowv2>>,").await;
+    client
+        .expect(OpCode::WindowChanged, json!({ "title": "" }))
+        .await;
+
+    requests.set(
+        "j/monitors",
+        &MONITORS.replace(r#""id": 2, "name": "2""#, r#""id": 1, "name": "1""#),
+    );
+    hyprland.send("workspace>>1").await;
+    hyprland.send("workspacev2>>1,1").await;
+
+    let workspaces = json!([
+        { "id": 1, "name": "1", "monitor": "DP-1", "windows": 1 },
+        { "id": 2, "name": "2", "monitor": "DP-1", "windows": 0 },
+    ]);
+    let monitors = json!([{ "name": "DP-1", "activeWorkspace": 1, "specialWorkspace": null, "focused": true }]);
+    client
+        .expect(
+            OpCode::WorkspaceState,
+            json!({ "workspaces": workspaces, "monitors": monitors, "focusedWindow": null }),
+        )
+        .await;
+
+    // The window is taken from the events, on the workspace active where it got focus
+    hyprland.send("activewindow>>foot,~").await;
+    hyprland.send("activewindowv2>>a1").await;
+    client
+        .expect(OpCode::WindowChanged, json!({ "title": "~" }))
+        .await;
+    client
+        .expect(
+            OpCode::WorkspaceState,
+            json!({
+                "workspaces": workspaces,
+                "monitors": monitors,
+                "focusedWindow": { "address": "0xa1", "class": "foot", "title": "~", "workspace": 1 },
+            }),
+        )
+        .await;
+
+    // Only the focused window's title is part of the state
+    hyprland.send("windowtitle>>b2").await;
+    hyprland.send("windowtitlev2>>b2,vim").await;
+    hyprland.send("windowtitle>>a1").await;
+    hyprland.send("windowtitlev2>>a1,~/src").await;
+    client
+        .expect(
+            OpCode::WorkspaceState,
+            json!({
+                "workspaces": workspaces,
+                "monitors": monitors,
+                "focusedWindow": { "address": "0xa1", "class": "foot", "title": "~/src", "workspace": 1 },
+            }),
+        )
+        .await;
+    client.expect_none(Duration::from_millis(200)).await;
+
+    // Queried on connect and for the workspace switch, never for the window
+    let queried = requests.received();
+    assert_eq!(
+        queried
+            .iter()
+            .filter(|request| *request == "j/activewindow")
+            .count(),
+        2,
+        "{:?}",
+        queried
+    );
+}
@@ -0,0 +1,158 @@
+use backend::hyprland_event::HyprlandEvent;
+use serde_json::{Value, json};
+
+fn parse(line: &str) -> Option<Value> {
+    let (name, data) = line.split_once(">>").unwrap();
+    HyprlandEvent::parse(name, data).map(|event| serde_json::to_value(event).unwrap())
+}
+
+#[test]
+fn parses_every_known_event() {
+    let cases = [
+        ("workspace>>2", json!({ "event": "workspace", "name": "2" })),
+        (
+            "workspacev2>>-98,special:magic",
+            json!({ "event": "workspacev2", "id": -98, "name": "special:magic" }),
+        ),
+        (
+            "focusedmon>>DP-1,chat",
+            json!({ "event": "focusedmon", "monitor": "DP-1", "workspace": "chat" }),
+        ),
+        (
+            "focusedmonv2>>eDP-1,3",
+            json!({ "event": "focusedmonv2", "monitor": "eDP-1", "workspaceId": 3 }),
+        ),
+        (
+            "activewindow>>firefox,Rust, the book - Firefox",
+            json!({ "event": "activewindow", "class": "firefox", "title": "Rust, the book - Firefox" }),
+        ),
+        (
+            "activewindowv2>>55d1c6c0e2a0",
+            json!({ "event": "activewindowv2", "address": "0x55d1c6c0e2a0" }),
+        ),
+        (
+            "activewindowv2>>",
+            json!({ "event": "activewindowv2", "address": null }),
+        ),
+        (
+            "fullscreen>>1",
+            json!({ "event": "fullscreen", "enabled": true }),
+        ),
+        (
+            "monitorremoved>>eDP-1",
+            json!({ "event": "monitorremoved", "name": "eDP-1" }),
+        ),
+        (
+            "monitoraddedv2>>1,eDP-1,BOE 0x0BCA, built in",
+            json!({ "event": "monitoraddedv2", "id": 1, "name": "eDP-1", "description": "BOE 0x0BCA, built in" }),
+        ),
+        (
+            "createworkspacev2>>4,chat",
+            json!({ "event": "createworkspacev2", "id": 4, "name": "chat" }),
+        ),
+        (
+            "destroyworkspace>>4",
+            json!({ "event": "destroyworkspace", "name": "4" }),
+        ),
+        (
+            "moveworkspacev2>>4,a,b,DP-1",
+            json!({ "event": "moveworkspacev2", "id": 4, "name": "a,b", "monitor": "DP-1" }),
+        ),
+        (
+            "renameworkspace>>4,notes",
+            json!({ "event": "renameworkspace", "id": 4, "name": "notes" }),
+        ),
+        (
+            "activespecial>>special:magic,DP-1",
+            json!({ "event": "activespecial", "name": "special:magic", "monitor": "DP-1" }),
+        ),
+        (
+            "activespecialv2>>,,DP-1",
+            json!({ "event": "activespecialv2", "id": null, "name": null, "monitor": "DP-1" }),
+        ),
+        (
+            "activelayout>>at-translated-set-2-keyboard,English (US)",
+            json!({ "event": "activelayout", "keyboard": "at-translated-set-2-keyboard", "layout": "English (US)" }),
+        ),
+        (
+            "openwindow>>5a1b,2,foot,~/src, again",
+            json!({ "event": "openwindow", "address": "0x5a1b", "workspace": "2", "class": "foot", "title": "~/src, again" }),
+        ),
+        (
+            "closewindow>>5a1b",
+            json!({ "event": "closewindow", "address": "0x5a1b" }),
+        ),
+        (
+            "movewindowv2>>5a1b,3,3",
+            json!({ "event": "movewindowv2", "address": "0x5a1b", "workspaceId": 3, "workspace": "3" }),
+        ),
+        (
+            "openlayer>>waybar",
+            json!({ "event": "openlayer", "namespace": "waybar" }),
+        ),
+        ("submap>>", json!({ "event": "submap", "name": null })),
+        (
+            "submap>>resize",
+            json!({ "event": "submap", "name": "resize" }),
+        ),
+        (
+            "changefloatingmode>>5a1b,1",
+            json!({ "event": "changefloatingmode", "address": "0x5a1b", "floating": true }),
+        ),
+        (
+            "urgent>>5a1b",
+            json!({ "event": "urgent", "address": "0x5a1b" }),
+        ),
+        (
+            "minimized>>5a1b,0",
+            json!({ "event": "minimized", "address": "0x5a1b", "minimized": false }),
+        ),
+        (
+            "screencast>>1,0",
+            json!({ "event": "screencast", "active": true, "window": false }),
+        ),
+        (
+            "windowtitlev2>>5a1b,nvim",
+            json!({ "event": "windowtitlev2", "address": "0x5a1b", "title": "nvim" }),
+        ),
+        (
+            "togglegroup>>1,5a1b,5a1c",
+            json!({ "event": "togglegroup", "open": true, "addresses": ["0x5a1b", "0x5a1c"] }),
+        ),
+        (
+            "lockgroups>>0",
+            json!({ "event": "lockgroups", "enabled": false }),
+        ),
+        (
+            "pin>>5a1b,1",
+            json!({ "event": "pin", "address": "0x5a1b", "pinned": true }),
+        ),
+        ("bell>>", json!({ "event": "bell", "address": null })),
+        ("configreloaded>>", json!({ "event": "configreloaded" })),
+        (
+            "somethingnew>>a,b",
+            json!({ "event": "other", "name": "somethingnew", "data": "a,b" }),
+        ),
+    ];
+
+    for (line, expected) in cases {
+        assert_eq!(parse(line), Some(expected), "{}", line);
+    }
+}
+
+#[test]
+fn rejects_events_missing_fields() {
+    for line in [
+        "workspacev2>>2",
+        "workspacev2>>two,2",
+        "focusedmonv2>>DP-1",
+        "fullscreen>>yes",
+        "monitoraddedv2>>1,eDP-1",
+        "openwindow>>5a1b,2,foot",
+        "movewindowv2>>5a1b,x,3",
+        "changefloatingmode>>5a1b",
+        "activespecialv2>>x,special:magic,DP-1",
+    ] {
+        assert_eq!(parse(line), None, "{}", line);
+    }
+}
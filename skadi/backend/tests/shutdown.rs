@@ -0,0 +1,133 @@
+#![cfg(all(feature = "spotify", feature = "volume"))]
+
+mod common;
+
+use backend::payload::OpCode;
+use common::{Harness, health, is_reaped, is_running};
+use serde_json::json;
+use std::os::unix::process::ExitStatusExt;
+
+// Both children keep running until killed, like the real `pactl subscribe` and `playerctl --follow`.
+// Each waits on a child of its own, which has to go along with it
+fn stub_followers(harness: &Harness) {
+    harness.stub(
+        "pactl",
+        r#"
+case "$1" in
+    get-sink-volume) echo 'Volume: front-left: 65536 /  50% / 0.00 dB' ;;
+    subscribe)
+        sleep 60 &
+        echo $! > "$STATE/pactl-child.pid"
+        echo $$ > "$STATE/pactl.pid"
+        wait
+        ;;
+esac
+"#,
+    );
+    harness.stub(
+        "playerctl",
+        r#"
+case "$*" in
+    *--follow*)
+        sleep 60 &
+        echo $! > "$STATE/playerctl-child.pid"
+        echo $$ > "$STATE/playerctl.pid"
+        wait
+        ;;
+    *) exit 1 ;;
+esac
+"#,
+    );
+}
+
+async fn wait_for_pid(harness: &Harness, name: &str) -> String {
+    for _ in 0..250 {
+        let pid = harness.read(name);
+
+        if !pid.is_empty() {
+            return pid;
+        }
+
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+
+    panic!("{} was never written", name);
+}
+
+async fn stops_on(signal: &str, number: i32) {
+    let mut harness = Harness::new();
+    stub_followers(&harness);
+    harness.start();
+
+    let mut client = harness
+        .subscribe(&[OpCode::Health, OpCode::VolumeEvent, OpCode::SpotifyEvent])
+        .await;
+
+    let children = [
+        ("pactl subscribe", wait_for_pid(&harness, "pactl.pid").await),
+        (
+            "playerctl --follow",
+            wait_for_pid(&harness, "playerctl.pid").await,
+        ),
+    ];
+    let grandchildren = [
+        ("pactl's child", harness.read("pactl-child.pid")),
+        ("playerctl's child", harness.read("playerctl-child.pid")),
+    ];
+    assert!(
+        children
+            .iter()
+            .chain(&grandchildren)
+            .all(|(_, pid)| is_running(pid))
+    );
+
+    let status = harness.signal(signal).await;
+    assert_eq!(status.code(), Some(128 + number), "{:?}", status.signal());
+
+    for (program, pid) in &children {
+        assert!(is_reaped(pid), "{} was not reaped", program);
+    }
+
+    for (program, pid) in &grandchildren {
+        assert!(!is_running(pid), "{} outlived the hub", program);
+    }
+
+    assert!(!harness.dir.join("skadi").join("backend.sock").exists());
+
+    // Everything up to the sources stopping still reaches the client before the connection closes
+    let mut stopped = Vec::new();
+
+    while let Some(payload) = client.try_next().await {
+        if payload.op == u16::from(OpCode::Health) {
+            let data = serde_json::to_value(&payload.data).unwrap();
+
+            if data["status"]["state"] == "stopped" {
+                stopped.push(data);
+            }
+        }
+    }
+
+    stopped.sort_by_key(|data| data["source"].to_string());
+    assert_eq!(
+        stopped,
+        [
+            health("spotify", json!({ "state": "stopped" }), 0),
+            health("volume", json!({ "state": "stopped" }), 0),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn stops_sources_and_children_on_sigterm() {
+    stops_on("TERM", 15).await;
+}
+
+#[tokio::test]
+async fn stops_sources_and_children_on_sigint() {
+    stops_on("INT", 2).await;
+}
+
+#[tokio::test]
+async fn stops_sources_and_children_on_sighup() {
+    stops_on("HUP", 1).await;
+}
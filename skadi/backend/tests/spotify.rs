@@ -0,0 +1,121 @@
+#![cfg(feature = "spotify")]
+
+mod common;
+
+use backend::payload::OpCode;
+use common::{Harness, error, health};
+use serde_json::{Value, json};
+
+const OPS: [OpCode; 3] = [OpCode::Error, OpCode::Health, OpCode::SpotifyEvent];
+
+// Answers metadata queries for one track, `--follow` prints a few changes and exits
+const PLAYERCTL: &str = r#"
+echo "$@" >> "$STATE/playerctl.log"
+case "$*" in
+    *--follow*)
+        echo 'Playing|Song|Artist|Album|1000|200000000|0.5|https://art/1'
+        echo 'Paused|Song|Artist|Album|2000|200000000|0.5|'
+        echo 'garbage'
+        exit 0
+        ;;
+    *metadata\ title) echo 'Song' ;;
+    *metadata\ artist) echo 'Artist' ;;
+    *metadata\ status) echo 'Playing' ;;
+    *metadata\ position) echo '1000' ;;
+    *metadata\ mpris:length) echo '200000000' ;;
+    *metadata\ volume) echo '0.5' ;;
+    *metadata\ *) echo 'No value' >&2; exit 1 ;;
+    *) ;;
+esac
+"#;
+
+fn track(kind: &str, status: &str, position: u64, artwork: Option<&str>) -> Value {
+    json!({
+        "type": kind,
+        "trackInfo": {
+            "title": "Song",
+            "artist": "Artist",
+            "album": if kind == "Request" { Value::Null } else { json!("Album") },
+            "status": status,
+            "position": position,
+            "duration": 200000000,
+            "volume": "0.5",
+            "artworkUrl": artwork,
+        },
+    })
+}
+
+#[tokio::test]
+async fn follows_playerctl_and_restarts_when_it_exits() {
+    let mut harness = Harness::new();
+    harness.stub("playerctl", PLAYERCTL);
+    harness.start();
+
+    let mut client = harness.subscribe(&OPS).await;
+
+    for restarts in 0..2 {
+        client
+            .expect(
+                OpCode::Health,
+                health("spotify", json!({ "state": "running" }), restarts),
+            )
+            .await;
+        client
+            .expect(
+                OpCode::SpotifyEvent,
+                track("Request", "Playing", 1000, None),
+            )
+            .await;
+        client
+            .expect(
+                OpCode::SpotifyEvent,
+                track("Playing", "Playing", 1000, Some("https://art/1")),
+            )
+            .await;
+        client
+            .expect(OpCode::SpotifyEvent, track("Paused", "Paused", 2000, None))
+            .await;
+        client
+            .expect(
+                OpCode::Error,
+                error(
+                    "spotify",
+                    "invalidOutput",
+                    "Unexpected output from playerctl: garbage",
+                    true,
+                ),
+            )
+            .await;
+        client
+            .expect(
+                OpCode::Error,
+                error("spotify", "sourceExited", "playerctl --follow exited", true),
+            )
+            .await;
+        client
+            .expect(
+                OpCode::Health,
+                health(
+                    "spotify",
+                    json!({ "state": "restarting", "retryInMs": 1000 * (restarts + 1) }),
+                    restarts,
+                ),
+            )
+            .await;
+    }
+}
+
+#[tokio::test]
+async fn runs_media_commands_on_the_player() {
+    let mut harness = Harness::new();
+    harness.stub("playerctl", PLAYERCTL);
+    harness.start();
+
+    let mut client = harness.subscribe(&[OpCode::Reply]).await;
+    let reply = client
+        .command("1", json!({ "type": "media", "action": "playPause" }))
+        .await;
+
+    assert!(reply.ok, "{:?}", reply.error);
+    assert_eq!(harness.read("playerctl.log"), "-p spotify play-pause\n");
+}
@@ -0,0 +1,38 @@
+#![cfg(feature = "sysinfo")]
+
+mod common;
+
+use backend::{event::Event, payload::OpCode};
+use common::{Harness, error};
+use std::time::Duration;
+
+#[tokio::test]
+async fn reports_a_missing_mount_once() {
+    let mut harness = Harness::new();
+    harness.config("[sysinfo]\npoll_interval = 1\nmount = \"/nothing/mounted/here\"");
+    harness.start();
+
+    let mut client = harness.subscribe(&[OpCode::Error, OpCode::Sysinfo]).await;
+
+    client
+        .expect(
+            OpCode::Error,
+            error(
+                "sysinfo",
+                "invalidConfig",
+                "Invalid config value for sysinfo.mount: nothing is mounted at /nothing/mounted/here",
+                true,
+            ),
+        )
+        .await;
+
+    // Still sampled, with the disk left out
+    for _ in 0..2 {
+        let Event::Sysinfo(sample) = client.next().await.data else {
+            panic!("Expected a sample");
+        };
+        assert_eq!((sample.disk_total, sample.disk_free), (0, 0));
+    }
+
+    client.expect_none(Duration::from_millis(500)).await;
+}
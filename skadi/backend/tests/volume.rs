@@ -0,0 +1,121 @@
+#![cfg(feature = "volume")]
+
+mod common;
+
+use backend::payload::OpCode;
+use common::{Harness, error, health};
+use serde_json::json;
+
+const OPS: [OpCode; 3] = [OpCode::Error, OpCode::Health, OpCode::VolumeEvent];
+
+// Every query reports 10% more than the last, `subscribe` prints two sink changes among other events and
+// exits. Muting fails the way pactl does when the sink is gone
+const PACTL: &str = r#"
+echo "$@" >> "$STATE/pactl.log"
+case "$1" in
+    get-sink-volume)
+        volume=$(( $(cat "$STATE/volume" 2>/dev/null || echo 0) + 10 ))
+        echo "$volume" > "$STATE/volume"
+        echo "Volume: front-left: 65536 /  $volume% / 0.00 dB,   front-right: 65536 /  $volume% / 0.00 dB"
+        ;;
+    subscribe)
+        echo "Event 'new' on client #42"
+        echo "Event 'change' on sink #0"
+        echo "Event 'change' on source #1"
+        echo "Event 'change' on sink #0"
+        ;;
+    set-sink-mute)
+        echo 'Failure: No such entity' >&2
+        exit 1
+        ;;
+esac
+"#;
+
+#[tokio::test]
+async fn reemits_on_sink_changes_and_restarts_when_pactl_exits() {
+    let mut harness = Harness::new();
+    harness.stub("pactl", PACTL);
+    harness.start();
+
+    let mut client = harness.subscribe(&OPS).await;
+
+    client
+        .expect(
+            OpCode::Health,
+            health("volume", json!({ "state": "running" }), 0),
+        )
+        .await;
+    client
+        .expect(OpCode::VolumeEvent, json!({ "volume": 10 }))
+        .await;
+    client
+        .expect(OpCode::VolumeEvent, json!({ "volume": 20 }))
+        .await;
+    client
+        .expect(OpCode::VolumeEvent, json!({ "volume": 30 }))
+        .await;
+    client
+        .expect(
+            OpCode::Error,
+            error("volume", "sourceExited", "pactl subscribe exited", true),
+        )
+        .await;
+    client
+        .expect(
+            OpCode::Health,
+            health(
+                "volume",
+                json!({ "state": "restarting", "retryInMs": 1000 }),
+                0,
+            ),
+        )
+        .await;
+    client
+        .expect(
+            OpCode::Health,
+            health("volume", json!({ "state": "running" }), 1),
+        )
+        .await;
+    client
+        .expect(OpCode::VolumeEvent, json!({ "volume": 40 }))
+        .await;
+}
+
+#[tokio::test]
+async fn runs_volume_commands_on_the_sink() {
+    let mut harness = Harness::new();
+    harness.stub("pactl", PACTL);
+    harness.start();
+
+    let mut client = harness.subscribe(&[OpCode::Reply]).await;
+
+    let reply = client
+        .command("set", json!({ "type": "volumeSet", "volume": 55 }))
+        .await;
+    assert!(reply.ok, "{:?}", reply.error);
+
+    let reply = client
+        .command("step", json!({ "type": "volumeStep", "delta": -5 }))
+        .await;
+    assert!(reply.ok, "{:?}", reply.error);
+
+    let reply = client
+        .command("mute", json!({ "type": "volumeMute" }))
+        .await;
+    let reply = serde_json::to_value(&reply).unwrap();
+    assert_eq!(
+        reply,
+        json!({
+            "id": "mute",
+            "ok": false,
+            "error": { "kind": "commandFailed", "message": "pactl failed: Failure: No such entity" },
+        })
+    );
+
+    assert_eq!(
+        harness.read("pactl.log"),
+        "set-sink-volume @DEFAULT_SINK@ 55%\n\
+         set-sink-volume @DEFAULT_SINK@ -5%\n\
+         set-sink-mute @DEFAULT_SINK@ toggle\n"
+    );
+}
@@ -0,0 +1,3 @@
+import { topbarFor } from "../topbar";
+
+export default topbarFor("DP-1");
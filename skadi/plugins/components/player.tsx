@@ -1,11 +1,11 @@
 import React, { useState } from "react";
-import { cn } from "../util";
+import { cn, sendCommand } from "../util";
 import type { Props } from "../types";
 import {
   OpCode,
   Payload,
   SpotifyEvent,
-  SpotifyEventType,
+  SpotifyEventKind,
   TrackInfo
 } from "../payloads";
 import { Pause, Play, SkipBack, SkipForward } from "lucide-react";
@@ -33,12 +33,12 @@ const Spotify: React.FC<Props> = ({ exec, useListen }) => {
   useListen<Payload<SpotifyEvent>>(
     "/home/svscagn/.config/skadi/scripts/backend",
     p => {
-      if (p.op !== OpCode.Spotify) return;
+      if (p.op !== OpCode.SpotifyEvent) return;
 
       switch (p.data.type) {
-        case SpotifyEventType.Playing:
-          if (!coverUrl || p.data.trackInfo?.artworkUrl !== coverUrl) {
-            setCoverUrl(p.data.trackInfo?.artworkUrl || null);
+        case SpotifyEventKind.Playing:
+          if (!coverUrl || p.data.trackInfo.artworkUrl !== coverUrl) {
+            setCoverUrl(p.data.trackInfo.artworkUrl || null);
           }
 
           if (!playing) {
@@ -48,14 +48,19 @@ const Spotify: React.FC<Props> = ({ exec, useListen }) => {
           setTrack(p.data.trackInfo);
           break;
 
-        case SpotifyEventType.Paused:
-        case SpotifyEventType.Stopped:
+        case SpotifyEventKind.Paused:
+        case SpotifyEventKind.Stopped:
           if (playing) {
             setPlaying(false);
           }
+
+          // The replayed state may be a pause, with no track seen before it
+          if (p.snapshot) {
+            setTrack(p.data.trackInfo);
+          }
           break;
 
-        case SpotifyEventType.Request:
+        case SpotifyEventKind.Request:
           setTrack(p.data.trackInfo);
           break;
       }
@@ -85,10 +90,7 @@ const Spotify: React.FC<Props> = ({ exec, useListen }) => {
           <SkipBack
           size={20}
             onClick={() =>
-              exec({
-                script: "playerctl",
-                args: ["--player=spotify", "previous"]
-              })
+              sendCommand(exec, { type: "media", action: "previous" })
             }
           />
         </button>
@@ -97,20 +99,14 @@ const Spotify: React.FC<Props> = ({ exec, useListen }) => {
             <Pause
               size={20}
               onClick={() =>
-                exec({
-                  script: "playerctl",
-                  args: ["--player=spotify", "pause"]
-                })
+                sendCommand(exec, { type: "media", action: "pause" })
               }
             />
           ) : (
             <Play
               size={20}
               onClick={() =>
-                exec({
-                  script: "playerctl",
-                  args: ["--player=spotify", "play"]
-                })
+                sendCommand(exec, { type: "media", action: "play" })
               }
             />
           )}
@@ -119,10 +115,7 @@ const Spotify: React.FC<Props> = ({ exec, useListen }) => {
           <SkipForward
             size={20}
             onClick={() =>
-              exec({
-                script: "playerctl",
-                args: ["--player=spotify", "next"]
-              })
+              sendCommand(exec, { type: "media", action: "next" })
             }
           />
         </button>
@@ -1,4 +1,4 @@
-import { useEffect, useState } from "react";
+import { useEffect, useRef, useState } from "react";
 import { Props } from "../types";
 import { bytesTo, cn } from "../util";
 import { ArrowDown, ArrowUp, Cpu, MemoryStick, Server } from "lucide-react";
@@ -148,6 +148,8 @@ const MiniChart: React.FC<{ data: NetworkDataPoint[] }> = ({ data }) => {
 
 const SysInfo: React.FC<Props> = ({ exec, useListen }) => {
   const [metrics, setMetrics] = useState<SysinfoPayload | null>(null);
+  const [rates, setRates] = useState({ up: 0, down: 0 });
+  const lastSample = useRef<number | null>(null);
   const [netHistory, setNetHistory] = useState<NetworkDataPoint[]>([
     { timestamp: Date.now(), up: 0, down: 0 }
   ]);
@@ -156,12 +158,22 @@ const SysInfo: React.FC<Props> = ({ exec, useListen }) => {
     "/home/svscagn/.config/skadi/scripts/backend",
     p => {
       if (p.op !== OpCode.Sysinfo) return;
+
+      // Network counters are bytes since the previous sample, so divide by the real interval
+      let elapsed =
+        lastSample.current === null ? 0 : p.mono - lastSample.current;
+      lastSample.current = p.mono;
+
+      let up = elapsed > 0 ? (p.data.networkTx * 1000) / elapsed : 0;
+      let down = elapsed > 0 ? (p.data.networkRx * 1000) / elapsed : 0;
+
       setMetrics(p.data);
+      setRates({ up, down });
       setNetHistory(prev => {
         let newPoint: NetworkDataPoint = {
-          timestamp: Date.now(),
-          up: p.data.networkTx,
-          down: p.data.networkRx
+          timestamp: p.time,
+          up,
+          down
         };
         let updatedHistory = [...prev, newPoint].slice(-30);
 
@@ -199,13 +211,13 @@ const SysInfo: React.FC<Props> = ({ exec, useListen }) => {
         <span className={cn("flex items-center gap-2 flex-shrink-0")}>
           <ArrowUp size={18} color="var(--cyan-9)" />
           <p className="text-sm w-16">
-            {formatNetworkBytes(metrics.networkTx)}
+            {formatNetworkBytes(rates.up)}
           </p>
         </span>
         <span className={cn("flex items-center gap-2 flex-shrink-0")}>
           <ArrowDown size={18} color="var(--plum-9)" />
           <p className="text-sm w-16">
-            {formatNetworkBytes(metrics.networkRx)}
+            {formatNetworkBytes(rates.down)}
           </p>
         </span>
         <div className={cn("flex items-center gap-1.5 min-w-0")}>
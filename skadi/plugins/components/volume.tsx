@@ -1,5 +1,5 @@
 import React, { useEffect, useState, useCallback, useRef } from "react";
-import { cn } from "../util";
+import { cn, sendCommand } from "../util";
 import { Section } from "./section";
 import { Volume1Icon, Volume2, Volume as VolumeIcon } from "lucide-react";
 import { Props } from "../types";
@@ -30,12 +30,7 @@ const Volume: React.FC<Props> = ({ exec, useListen }) => {
           console.log(`Executing volume change: ${value}%`);
           lastVolumeChangeRef.current = now;
 
-          // Use the most basic exec call possible
-          exec({
-            script: "pactl",
-            args: ["set-sink-volume", "@DEFAULT_SINK@", `${value}%`],
-            resolves: false
-          });
+          sendCommand(exec, { type: "volumeSet", volume: value });
         }
       }, 200);
     },
@@ -45,33 +40,11 @@ const Volume: React.FC<Props> = ({ exec, useListen }) => {
   useListen<Payload<VolumeEvent>>(
     "/home/svscagn/.config/skadi/scripts/backend",
     p => {
-      if (p.op !== OpCode.Volume) return;
+      if (p.op !== OpCode.VolumeEvent) return;
       setVolume(p.data.volume);
     }
   );
 
-  useEffect(() => {
-    const fetchVolume = async () => {
-      try {
-        let result = await exec<string>({
-          script: "sh",
-          args: [
-            "-c",
-            "pactl get-sink-volume @DEFAULT_SINK@ | awk '{print $5}' | sed 's/[%,]//g'"
-          ]
-        });
-
-        if (result) {
-          setVolume(Math.min(parseInt(result.trim(), 10), 100));
-        }
-      } catch (error) {
-        console.error("Failed to fetch volume:", error);
-      }
-    };
-
-    fetchVolume();
-  }, [exec]);
-
   // Cleanup timeout on unmount
   useEffect(() => {
     return () => {
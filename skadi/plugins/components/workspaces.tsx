@@ -1,80 +1,46 @@
-import React, { useEffect, useState } from "react";
-import { cn } from "../util";
+import React, { useState } from "react";
+import { Layers } from "lucide-react";
+import { cn, sendCommand } from "../util";
 import { Props } from "../types";
-import { OpCode, Payload, Workspace, WorkspaceEventType } from "../payloads";
+import { OpCode, Payload, WorkspaceInfo, WorkspaceState } from "../payloads";
 
-const Workspaces: React.FC<Props> = ({ exec, useListen }) => {
-  const [active, setActive] = useState<number>(1);
-  const [workspaces, setWorkspaces] = useState<Set<number>>(new Set([1]));
-
-  // Get initial workspace state on mount
-  useEffect(() => {
-    const getInitialWorkspaces = async () => {
-      let result = await exec<string>({
-        script: "hyprctl",
-        args: ["workspaces", "-j"]
-      });
-
-      if (!result) {
-        console.error("Failed to get initial workspace state");
-        return;
-      }
-
-      let workspaceData = JSON.parse(result);
-      let workspaceIds = workspaceData.map((ws: any) => ws.id);
-
-      setWorkspaces(new Set(workspaceIds));
-
-      // Get current active workspace
-      let activeResult = await exec<string>({
-        script: "hyprctl",
-        args: ["activeworkspace", "-j"]
-      });
-
-      if (!activeResult) {
-        console.error("Failed to get active workspace");
-        return;
-      }
-
-      let activeData = JSON.parse(activeResult);
+const SPECIAL = "special:";
 
-      setActive(activeData.id);
-    };
+type WorkspacesProps = Props & {
+  // Only the workspaces on this monitor are shown
+  monitor: string;
+};
 
-    getInitialWorkspaces();
-  }, [exec]);
+const Workspaces: React.FC<WorkspacesProps> = ({
+  exec,
+  useListen,
+  monitor
+}) => {
+  const [active, setActive] = useState<number>(1);
+  const [focused, setFocused] = useState<boolean>(true);
+  const [workspaces, setWorkspaces] = useState<WorkspaceInfo[]>([]);
+  // Special workspaces, the scratchpads, can be shown on any monitor
+  const [specials, setSpecials] = useState<WorkspaceInfo[]>([]);
+  const [shownSpecial, setShownSpecial] = useState<number | null>(null);
 
-  useListen<Payload<Workspace>>(
+  // The backend sends the full state on connect and whenever it changes
+  useListen<Payload<WorkspaceState>>(
     "/home/svscagn/.config/skadi/scripts/backend",
     p => {
-      if (p.op !== OpCode.Workspace) return;
+      if (p.op !== OpCode.WorkspaceState) return;
 
-      switch (p.data.type) {
-        case WorkspaceEventType.Moved:
-          setActive(p.data.id);
-          break;
-        case WorkspaceEventType.Created:
-          setWorkspaces(prev => new Set([...prev, p.data.id]));
-          break;
-        case WorkspaceEventType.Destroyed:
-          setWorkspaces(prev => {
-            const newSet = new Set(prev);
-            newSet.delete(p.data.id);
-            return newSet;
-          });
-          // If the destroyed workspace was active, switch to the first available workspace
-          if (p.data.id === active) {
-            setWorkspaces(currentWorkspaces => {
-              const remaining = Array.from(currentWorkspaces).filter(
-                id => id !== p.data.id
-              );
-              if (remaining.length > 0) {
-                setActive(Math.min(...remaining));
-              }
-              return new Set(remaining);
-            });
-          }
-          break;
+      setWorkspaces(
+        p.data.workspaces.filter(
+          ws => ws.monitor === monitor && !ws.name.startsWith(SPECIAL)
+        )
+      );
+      setSpecials(p.data.workspaces.filter(ws => ws.name.startsWith(SPECIAL)));
+
+      const own = p.data.monitors.find(m => m.name === monitor);
+      if (own) {
+        setActive(own.activeWorkspace);
+        setShownSpecial(own.specialWorkspace);
+        setFocused(own.focused);
       }
     }
   );
@@ -90,33 +56,52 @@ const Workspaces: React.FC<Props> = ({ exec, useListen }) => {
       )}
     >
       <div className="flex gap-1">
-        {Array.from(workspaces)
-          .sort((a, b) => a - b)
-          .map(workspaceNum => {
-            const isActive = workspaceNum === active;
+        {workspaces.map(workspace => {
+          const isActive = workspace.id === active;
 
-            return (
-              <div
-                key={workspaceNum}
-                className={cn(
-                  "px-2 py-0.5 rounded text-sm font-medium cursor-pointer",
-                  "transition-all duration-300 ease-out",
-                  isActive
+          return (
+            <div
+              key={workspace.id}
+              className={cn(
+                "px-2 py-0.5 rounded text-sm font-medium cursor-pointer",
+                "transition-all duration-300 ease-out",
+                isActive
+                  ? focused
                     ? "bg-white/20 text-white scale-105"
-                    : "text-white/50 hover:text-white/70"
-                )}
-                onClick={() =>
-                  exec({
-                    script: "hyprctl",
-                    args: ["dispatch", "workspace", workspaceNum.toString()]
-                  })
-                }
-              >
-                {workspaceNum}
-              </div>
-            );
-          })}
+                    : "bg-white/10 text-white/70"
+                  : "text-white/50 hover:text-white/70"
+              )}
+              onClick={() =>
+                sendCommand(exec, { type: "workspace", id: workspace.id })
+              }
+            >
+              {workspace.name}
+            </div>
+          );
+        })}
       </div>
+      {specials.map(special => {
+        const isShown = special.id === shownSpecial;
+
+        return (
+          <div
+            key={special.id}
+            title={special.name.slice(SPECIAL.length)}
+            className={cn(
+              "flex items-center px-1 py-0.5 rounded cursor-pointer",
+              "transition-all duration-300 ease-out",
+              isShown
+                ? "bg-white/20 text-white"
+                : "text-white/50 hover:text-white/70"
+            )}
+            onClick={() =>
+              sendCommand(exec, { type: "workspace", id: special.id })
+            }
+          >
+            <Layers size={16} />
+          </div>
+        );
+      })}
     </div>
   );
 };
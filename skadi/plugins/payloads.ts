@@ -1,80 +1,57 @@
-enum OpCode {
-  Error = 0,
-  Sysinfo = 1,
-  WindowChanged = 2,
-  Workspace = 3,
-  Spotify = 4,
-  Volume = 5
-}
-
-type Payload<T> = {
-  op: OpCode;
-  data: T;
-};
-
-type SysinfoPayload = {
-  cpuUsage: number;
-  memoryUsed: number;
-  memoryTotal: number;
-  memoryFree: number;
-  swapUsed: number;
-  swapTotal: number;
-  networkRx: number;
-  networkTx: number;
-  diskRead: number;
-  diskWrite: number;
-  diskUsage: number;
-  diskTotal: number;
-  diskFree: number;
-};
-
-enum WorkspaceEventType {
-  Moved = "Moved",
-  Created = "Created",
-  Destroyed = "Destroyed"
-}
-
-type Workspace = {
-  id: number;
-  type: WorkspaceEventType;
-};
-
-type TrackInfo = {
-  length: number;
-  title: string | null;
-  artist: string | null;
-  album: string | null;
-  status: string;
-  position: number | null;
-  duration: number | null;
-  volume: string | null;
-  artworkUrl: string | null;
-};
-
-enum SpotifyEventType {
-  Request = "Request",
-  Playing = "Playing",
-  Paused = "Paused",
-  Stopped = "Stopped"
-}
-
-type SpotifyEvent = {
-  type: SpotifyEventType;
-  trackInfo: TrackInfo | null;
-};
-
-type VolumeEvent = {
-  volume: number;
-};
-
-export {
-  OpCode,
-  Payload,
-  SysinfoPayload,
-  WorkspaceEventType,
-  Workspace,
-  TrackInfo,
-  SpotifyEvent,
-  SpotifyEventType,
-  VolumeEvent
-};
+// Generated by `backend codegen` from the Rust types, do not edit by hand
+
+export const PROTOCOL_VERSION = 1;
+
+export enum OpCode { "Error" = 0, "Sysinfo" = 1, "WindowChanged" = 2, "Workspace" = 3, "SpotifyEvent" = 4, "VolumeEvent" = 5, "Reply" = 6, "Health" = 7, "Hello" = 8, "Custom" = 9, "WorkspaceState" = 10, "Hyprland" = 11 }
+
+export type Meta = { version: number, seq: number, time: number, mono: number, };
+
+export type Payload<T> = { op: OpCode, snapshot?: boolean, data: T, version: number, seq: number, time: number, mono: number, };
+
+export type Hello = { version: number, sources: Array<string>, };
+
+export type BackendError = { kind: string, message: string, };
+
+export type ErrorPayload = { module: string, retrying: boolean, kind: string, message: string, };
+
+export type SysinfoPayload = { cpuUsage: number, memoryUsed: number, memoryTotal: number, memoryFree: number, swapUsed: number, swapTotal: number, networkRx: number, networkTx: number, diskRead: number, diskWrite: number, diskUsage: number, diskTotal: number, diskFree: number, };
+
+export type WindowChanged = { title: string, };
+
+export enum WorkspaceEventKind { "Moved" = "Moved", "Created" = "Created", "Destroyed" = "Destroyed", "Reassigned" = "Reassigned", "Focused" = "Focused", "MonitorRemoved" = "MonitorRemoved", "Renamed" = "Renamed", "SpecialShown" = "SpecialShown", "SpecialHidden" = "SpecialHidden" }
+
+export type Workspace = { type: WorkspaceEventKind, id: number, name: string, monitor: string, };
+
+export type WorkspaceInfo = { id: number, name: string, monitor: string, windows: number, };
+
+export type MonitorInfo = { name: string, activeWorkspace: number, specialWorkspace: number | null, focused: boolean, };
+
+export type WindowInfo = { address: string, class: string, title: string, workspace: number, };
+
+export type WorkspaceState = { workspaces: Array<WorkspaceInfo>, monitors: Array<MonitorInfo>, focusedWindow: WindowInfo | null, };
+
+export type HyprlandEvent = { "event": "workspace", name: string, } | { "event": "workspacev2", id: number, name: string, } | { "event": "focusedmon", monitor: string, workspace: string, } | { "event": "focusedmonv2", monitor: string, workspaceId: number, } | { "event": "activewindow", class: string, title: string, } | { "event": "activewindowv2", address: string | null, } | { "event": "fullscreen", enabled: boolean, } | { "event": "monitorremoved", name: string, } | { "event": "monitorremovedv2", id: number, name: string, description: string, } | { "event": "monitoradded", name: string, } | { "event": "monitoraddedv2", id: number, name: string, description: string, } | { "event": "createworkspace", name: string, } | { "event": "createworkspacev2", id: number, name: string, } | { "event": "destroyworkspace", name: string, } | { "event": "destroyworkspacev2", id: number, name: string, } | { "event": "moveworkspace", name: string, monitor: string, } | { "event": "moveworkspacev2", id: number, name: string, monitor: string, } | { "event": "renameworkspace", id: number, name: string, } | { "event": "activespecial", name: string | null, monitor: string, } | { "event": "activespecialv2", id: number | null, name: string | null, monitor: string, } | { "event": "activelayout", keyboard: string, layout: string, } | { "event": "openwindow", address: string, workspace: string, class: string, title: string, } | { "event": "closewindow", address: string, } | { "event": "movewindow", address: string, workspace: string, } | { "event": "movewindowv2", address: string, workspaceId: number, workspace: string, } | { "event": "openlayer", namespace: string, } | { "event": "closelayer", namespace: string, } | { "event": "submap", name: string | null, } | { "event": "changefloatingmode", address: string, floating: boolean, } | { "event": "urgent", address: string, } | { "event": "minimized", address: string, minimized: boolean, } | { "event": "screencast", active: boolean, window: boolean, } | { "event": "windowtitle", address: string, } | { "event": "windowtitlev2", address: string, title: string, } | { "event": "togglegroup", open: boolean, addresses: Array<string>, } | { "event": "moveintogroup", address: string, } | { "event": "moveoutofgroup", address: string, } | { "event": "ignoregrouplock", enabled: boolean, } | { "event": "lockgroups", enabled: boolean, } | { "event": "pin", address: string, pinned: boolean, } | { "event": "bell", address: string | null, } | { "event": "configreloaded" } | { "event": "other", name: string, data: string, };
+
+export type TrackInfo = { title: string | null, artist: string | null, album: string | null, status: string, position: number | null, duration: number | null, volume: string | null, artworkUrl: string | null, };
+
+export enum SpotifyEventKind { "Request" = "Request", "Playing" = "Playing", "Paused" = "Paused", "Stopped" = "Stopped" }
+
+export type SpotifyEvent = { type: SpotifyEventKind, trackInfo: TrackInfo, };
+
+export type VolumeEvent = { volume: number, };
+
+export type CustomEvent = { source: string, value: unknown, };
+
+export type HealthStatus = { "state": "running" } | { "state": "restarting", retryInMs: number, } | { "state": "failed" } | { "state": "stopped" };
+
+export type SourceHealth = { source: string, status: HealthStatus, restarts: number, };
+
+export type MediaAction = "play" | "pause" | "playPause" | "next" | "previous";
+
+export type Command = { "type": "workspace", id: number, } | { "type": "media", action: MediaAction, } | { "type": "volumeSet", volume: number, } | { "type": "volumeStep", delta: number, } | { "type": "volumeMute" };
+
+export type CommandRequest = { id: string, command: Command, };
+
+export type CommandReply = { id: string | null, ok: boolean, error: BackendError | null, };
+
+export type ClientMessage = { "type": "subscribe", ops: Array<OpCode> | null, } | { "type": "command" } & CommandRequest;
@@ -1,6 +1,6 @@
-import React, { use, useEffect } from "react";
+import React, { use, useEffect, useState } from "react";
 import { Props } from "./types";
-import { cn } from "./util";
+import { BACKEND, cn } from "./util";
 import { Clock } from "./components/clock";
 import { SysInfo } from "./components/sysinfo";
 import { Workspaces } from "./components/workspaces";
@@ -8,8 +8,21 @@ import { Spotify } from "./components/player";
 import { Power } from "lucide-react";
 import { Section } from "./components/section";
 import { Volume } from "./components/volume";
+import { Hello, OpCode, Payload, PROTOCOL_VERSION } from "./payloads";
+
+type TopbarProps = Props & {
+  // The monitor the bar's window is on, see `config.jsonc`
+  monitor: string;
+};
+
+const Topbar: React.FC<TopbarProps> = ({ exec, useListen, monitor }) => {
+  const [backendVersion, setBackendVersion] = useState<number | null>(null);
+
+  useListen<Payload<Hello>>(BACKEND, p => {
+    if (p.op !== OpCode.Hello) return;
+    setBackendVersion(p.data.version);
+  });
 
-const Topbar: React.FC<Props> = ({ exec, useListen }) => {
   useEffect(() => {
     exec({
       script: "/home/svscagn/.config/skadi/scripts/backend",
@@ -18,6 +31,14 @@ const Topbar: React.FC<Props> = ({ exec, useListen }) => {
     });
   }, []);
 
+  if (backendVersion !== null && backendVersion !== PROTOCOL_VERSION) {
+    return (
+      <div className={cn("w-full h-full", "flex items-center", "text-white")}>
+        Backend speaks protocol {backendVersion}, expected {PROTOCOL_VERSION}
+      </div>
+    );
+  }
+
   return (
     <div
       className={cn(
@@ -30,7 +51,7 @@ const Topbar: React.FC<Props> = ({ exec, useListen }) => {
       )}
     >
       <div className={cn("flex items-center gap-4", "py-2")}>
-        <Workspaces exec={exec} useListen={useListen} />
+        <Workspaces exec={exec} useListen={useListen} monitor={monitor} />
         <Spotify exec={exec} useListen={useListen} />
         <Volume exec={exec} useListen={useListen} />
       </div>
@@ -47,4 +68,9 @@ const Topbar: React.FC<Props> = ({ exec, useListen }) => {
   );
 };
 
-export default Topbar;
+// One bar per monitor, each entry in `config.jsonc` loads the file for its own
+const topbarFor =
+  (monitor: string): React.FC<Props> =>
+  props => <Topbar {...props} monitor={monitor} />;
+
+export { topbarFor };
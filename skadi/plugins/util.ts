@@ -1,7 +1,12 @@
 import type { ClassValue } from "clsx";
+import type { Props } from "./types";
+import type { Command, CommandRequest } from "./payloads";
 
 import { clsx } from "clsx";
 import { twMerge } from "tailwind-merge";
+import { v4 as uuidv4 } from "uuid";
+
+export const BACKEND = "/home/svscagn/.config/skadi/scripts/backend";
 
 export const cn = (...inputs: ClassValue[]) => {
   return twMerge(clsx(inputs));
@@ -19,3 +24,13 @@ export const bytesTo = (b: number, unit: "KB" | "MB" | "GB" | "TB"): number => {
       return b / (1024 * 1024 * 1024 * 1024);
   }
 };
+
+export const sendCommand = (exec: Props["exec"], command: Command) => {
+  let request: CommandRequest = { id: uuidv4(), command };
+
+  return exec({
+    script: BACKEND,
+    args: ["send", JSON.stringify(request)],
+    resolves: false
+  });
+};